@@ -1,14 +1,72 @@
+use std::{env, fs, path::PathBuf, process::Command};
+
 const COMMANDS: &[&str] = &[
     "sign_zome_call",
     "get_locales",
     "open_app",
     "list_apps",
+    "list_agents",
     "is_holochain_ready",
+    "get_app_auth_token",
+    "call_zome",
+    "set_app_notifications_muted",
+    "provide_membrane_proof",
+    "create_clone_cell",
+    "enable_clone_cell",
+    "disable_clone_cell",
+    "delete_clone_cell",
 ];
 
 fn main() {
+    build_zome_call_signer();
+
     tauri_plugin::Builder::new(COMMANDS)
         // .android_path("android")
         // .ios_path("ios")
         .build();
 }
+
+/// Builds the zome call signer from `packages/signer` into `OUT_DIR`
+///
+/// Rollup writes its output into `OUT_DIR` too, so that the source tree is left untouched.
+/// Falls back to the prebuilt `zome-call-signer.js` when the package is not available (e.g. when building from crates.io)
+/// or can't be built (e.g. no pnpm or no node_modules), or when `SKIP_ZOME_CALL_SIGNER_BUILD` is set
+fn build_zome_call_signer() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let out_path = out_dir.join("zome-call-signer.js");
+    let prebuilt_path = manifest_dir.join("zome-call-signer.js");
+    let signer_package = manifest_dir.join("../../packages/signer");
+
+    println!("cargo:rerun-if-changed={}", prebuilt_path.display());
+    println!("cargo:rerun-if-env-changed=SKIP_ZOME_CALL_SIGNER_BUILD");
+
+    if signer_package.join("package.json").exists()
+        && env::var("SKIP_ZOME_CALL_SIGNER_BUILD").is_err()
+    {
+        println!(
+            "cargo:rerun-if-changed={}",
+            signer_package.join("src").display()
+        );
+
+        let rollup_out_dir = out_dir.join("signer-dist");
+        let status = Command::new("pnpm")
+            .args(["exec", "rollup", "-c", "rollup.config.js", "--dir"])
+            .arg(&rollup_out_dir)
+            .current_dir(&signer_package)
+            .status();
+
+        match status {
+            Ok(status) if status.success() => {
+                fs::copy(rollup_out_dir.join("index.js"), &out_path)
+                    .expect("Failed to copy the built zome call signer");
+                return;
+            }
+            _ => println!(
+                "cargo:warning=Could not build the zome call signer from packages/signer, using the prebuilt zome-call-signer.js"
+            ),
+        }
+    }
+
+    fs::copy(&prebuilt_path, &out_path).expect("Failed to copy the prebuilt zome call signer");
+}
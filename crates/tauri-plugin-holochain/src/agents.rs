@@ -0,0 +1,223 @@
+use std::sync::Arc;
+
+use holochain_client::{AdminWebsocket, AgentPubKey, InstalledAppId};
+use lair_keystore_api::{lair_client::LairEntryInfo, LairClient};
+use serde::{Deserialize, Serialize};
+use tauri::Runtime;
+
+use crate::{
+    recovery::{AGENT_KEYS_INDEX, MASTER_SEED_TAG, SEED_TRANSPORT_TAG},
+    HolochainPlugin,
+};
+
+/// Where to get the agent key for an app that is being installed from
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentKeySource {
+    /// Generate a new agent key, only used by this app
+    #[default]
+    Generate,
+    /// Use an agent key that already exists in the keystore, e.g. to have the same identity across apps
+    Existing { agent_pub_key: AgentPubKey },
+    /// Derive the agent key from the seed with the given tag in the keystore
+    ///
+    /// The same seed and derivation path always result in the same agent key, so this can be used to
+    /// have multiple identities (profiles) that are recoverable from a single seed
+    DerivedFromSeed {
+        seed_tag: String,
+        derivation_path: Vec<u32>,
+    },
+}
+
+/// An agent key stored in the keystore
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LocalAgent {
+    /// Tag of the seed for the agent in the keystore
+    pub tag: String,
+    pub agent_pub_key: AgentPubKey,
+    /// Whether the seed is protected by an additional passphrase
+    pub deep_locked: bool,
+    /// The installed apps that use this agent
+    pub installed_apps: Vec<InstalledAppId>,
+}
+
+impl<R: Runtime> HolochainPlugin<R> {
+    /// Lists the agent keys in the keystore, along with the apps that use each of them
+    pub async fn list_agents(&self) -> crate::Result<Vec<LocalAgent>> {
+        let entries = self
+            .lair_client()?
+            .list_entries()
+            .await
+            .map_err(|err| crate::Error::LairError(err))?;
+
+        let admin_ws = self.admin_websocket().await?;
+        let apps = admin_ws
+            .list_apps(None)
+            .await
+            .map_err(|err| crate::Error::ConductorApiError(err))?;
+
+        let agents = entries
+            .into_iter()
+            .filter_map(|entry| match entry {
+                LairEntryInfo::Seed { tag, seed_info } => Some((tag, seed_info, false)),
+                LairEntryInfo::DeepLockedSeed { tag, seed_info } => Some((tag, seed_info, true)),
+                _ => None,
+            })
+            .filter(|(tag, _, _)| is_agent_seed(tag))
+            .map(|(tag, seed_info, deep_locked)| {
+                let agent_pub_key = AgentPubKey::from_raw_32(seed_info.ed25519_pub_key.to_vec());
+                let installed_apps = apps
+                    .iter()
+                    .filter(|app| app.agent_pub_key.eq(&agent_pub_key))
+                    .map(|app| app.installed_app_id.clone())
+                    .collect();
+
+                LocalAgent {
+                    tag: tag.to_string(),
+                    agent_pub_key,
+                    deep_locked,
+                    installed_apps,
+                }
+            })
+            .collect();
+
+        Ok(agents)
+    }
+
+    /// Returns the agent key to install an app with from the given source
+    pub(crate) async fn resolve_agent_key(
+        &self,
+        admin_ws: &AdminWebsocket,
+        agent_key_source: AgentKeySource,
+    ) -> crate::Result<AgentPubKey> {
+        match agent_key_source {
+            AgentKeySource::Generate => admin_ws
+                .generate_agent_pub_key()
+                .await
+                .map_err(|err| crate::Error::ConductorApiError(err)),
+            AgentKeySource::Existing { agent_pub_key } => {
+                let agent_exists = self
+                    .list_agents()
+                    .await?
+                    .iter()
+                    .any(|agent| agent.agent_pub_key.eq(&agent_pub_key));
+                if !agent_exists {
+                    return Err(crate::Error::AgentNotFound(agent_pub_key));
+                }
+                Ok(agent_pub_key)
+            }
+            AgentKeySource::DerivedFromSeed {
+                seed_tag,
+                derivation_path,
+            } => derive_agent_key(&self.lair_client()?, seed_tag, derivation_path).await,
+        }
+    }
+
+    pub(crate) fn lair_client(&self) -> crate::Result<LairClient> {
+        Ok(self
+            .holochain_runtime
+            .conductor_handle()?
+            .keystore()
+            .lair_client()
+            .clone())
+    }
+}
+
+/// Whether the seed with the given tag in the keystore is the seed of an agent
+///
+/// The seeds used by the plugin itself and the keys derived from the master seed for other purposes
+/// (e.g. device keys) are not agents, only the ones derived under the agents index of the master seed are
+fn is_agent_seed(tag: &str) -> bool {
+    if tag == MASTER_SEED_TAG || tag == SEED_TRANSPORT_TAG {
+        return false;
+    }
+
+    match tag.strip_prefix(&format!("{MASTER_SEED_TAG}#")) {
+        Some(derivation_path) => {
+            let path: Vec<&str> = derivation_path.split('/').collect();
+            path.len() == 2
+                && path[0] == AGENT_KEYS_INDEX.to_string()
+                && path[1].parse::<u32>().is_ok()
+        }
+        None => true,
+    }
+}
+
+/// Tag in the keystore for the seed derived from the given seed and derivation path
+pub fn derived_seed_tag(seed_tag: &str, derivation_path: &[u32]) -> String {
+    let path: Vec<String> = derivation_path.iter().map(|i| i.to_string()).collect();
+    format!("{seed_tag}#{}", path.join("/"))
+}
+
+/// Derives an agent key from the seed with the given tag, reusing it if it was already derived
+pub(crate) async fn derive_agent_key(
+    lair_client: &LairClient,
+    seed_tag: String,
+    derivation_path: Vec<u32>,
+) -> crate::Result<AgentPubKey> {
+    let derived_tag: Arc<str> = derived_seed_tag(&seed_tag, &derivation_path).into();
+
+    if let Ok(LairEntryInfo::Seed { seed_info, .. }) =
+        lair_client.get_entry(derived_tag.clone()).await
+    {
+        return Ok(AgentPubKey::from_raw_32(seed_info.ed25519_pub_key.to_vec()));
+    }
+
+    let seed_info = lair_client
+        .derive_seed(
+            seed_tag.into(),
+            None,
+            derived_tag,
+            None,
+            derivation_path.into_boxed_slice(),
+        )
+        .await
+        .map_err(|err| crate::Error::LairError(err))?;
+
+    Ok(AgentPubKey::from_raw_32(seed_info.ed25519_pub_key.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derived_seed_tags_are_unique_per_path() {
+        assert_eq!(derived_seed_tag("root", &[0]), "root#0");
+        assert_eq!(derived_seed_tag("root", &[1, 2]), "root#1/2");
+        assert_ne!(
+            derived_seed_tag("root", &[1, 2]),
+            derived_seed_tag("root", &[12])
+        );
+    }
+
+    #[test]
+    fn only_agent_seeds_are_listed_as_agents() {
+        assert!(is_agent_seed(&derived_seed_tag(
+            MASTER_SEED_TAG,
+            &[AGENT_KEYS_INDEX, 0]
+        )));
+        assert!(is_agent_seed(&derived_seed_tag(
+            MASTER_SEED_TAG,
+            &[AGENT_KEYS_INDEX, 7]
+        )));
+        assert!(is_agent_seed(&derived_seed_tag("profiles", &[0, 3])));
+        assert!(is_agent_seed("generated-agent"));
+
+        assert!(!is_agent_seed(MASTER_SEED_TAG));
+        assert!(!is_agent_seed(SEED_TRANSPORT_TAG));
+        // Device keys
+        assert!(!is_agent_seed(&derived_seed_tag(MASTER_SEED_TAG, &[0, 0])));
+        assert!(!is_agent_seed(&derived_seed_tag(MASTER_SEED_TAG, &[0, 2])));
+        // Any other derivation of the master seed
+        assert!(!is_agent_seed(&derived_seed_tag(
+            MASTER_SEED_TAG,
+            &[AGENT_KEYS_INDEX]
+        )));
+        assert!(!is_agent_seed(&derived_seed_tag(
+            MASTER_SEED_TAG,
+            &[AGENT_KEYS_INDEX, 0, 1]
+        )));
+        assert!(!is_agent_seed(&derived_seed_tag(MASTER_SEED_TAG, &[2, 0])));
+    }
+}
@@ -0,0 +1,648 @@
+use std::collections::{HashMap, HashSet};
+
+use holochain_client::{AdminWebsocket, InstalledAppId};
+use holochain_conductor_api::AppInterfaceInfo;
+use holochain_types::websocket::AllowedOrigins;
+use tauri::Runtime;
+
+use crate::{origin::happ_origin, HolochainPlugin};
+
+/// How many shared app interfaces can be attached at the same time, counting the replaced ones that are still in use
+const MAX_SHARED_APP_INTERFACES: usize = 3;
+
+/// How app interfaces are attached to the conductor to serve the app websockets of the installed apps
+#[derive(Clone, Debug, Default)]
+pub enum AppInterfaceStrategy {
+    /// Attach one app interface per app, which only accepts connections for that app
+    PerApp,
+    /// Share one app interface among all apps, relying on the per-app auth tokens to tell them apart
+    ///
+    /// The shared interface allows the origins of the apps installed when it's attached. Since the conductor can't change
+    /// the origins allowed by an attached interface, apps installed afterwards get a new shared interface that allows all the apps.
+    /// The replaced interface is kept until the windows that were open when it was replaced are closed, but at most
+    /// 3 shared interfaces are attached at the same time: beyond that, the oldest one is detached and its windows
+    /// get a new app websocket port and auth token with the `app-websocket-auth-reissued` event
+    #[default]
+    Shared,
+}
+
+/// Bookkeeping of the app interfaces attached by the plugin
+#[derive(Default)]
+pub(crate) struct AppInterfaces {
+    /// Port of the app interface assigned to each app
+    apps_ports: HashMap<InstalledAppId, u16>,
+    /// Origins allowed by each of the attached app interfaces
+    allowed_origins: HashMap<u16, AllowedOrigins>,
+    /// Port of the interface shared among apps, if one is attached
+    shared_port: Option<u16>,
+    /// Shared interfaces that were replaced but may still be in use, oldest first
+    retiring: Vec<RetiringInterface>,
+}
+
+/// A shared app interface that was replaced by a new one
+pub(crate) struct RetiringInterface {
+    pub(crate) port: u16,
+    /// The apps that were served by the interface
+    pub(crate) app_ids: HashSet<InstalledAppId>,
+    /// The windows of those apps that were open when the interface was replaced, and may still be connected through it
+    pub(crate) window_labels: HashSet<String>,
+}
+
+impl AppInterfaces {
+    pub(crate) fn get_app_port(&self, app_id: &InstalledAppId) -> Option<u16> {
+        self.apps_ports.get(app_id).cloned()
+    }
+
+    fn get_port_allowing_origin(&self, origin: &String) -> Option<u16> {
+        self.allowed_origins
+            .iter()
+            .find(|(_, allowed_origins)| allows_origin(allowed_origins, origin))
+            .map(|(port, _)| *port)
+    }
+
+    fn add_interface(&mut self, port: u16, allowed_origins: AllowedOrigins) {
+        self.allowed_origins.insert(port, allowed_origins);
+    }
+
+    fn add_shared_interface(&mut self, port: u16, allowed_origins: AllowedOrigins) {
+        self.add_interface(port, allowed_origins);
+        self.shared_port = Some(port);
+    }
+
+    fn assign(&mut self, app_id: InstalledAppId, port: u16) {
+        self.apps_ports.insert(app_id, port);
+    }
+
+    /// Makes the given interface the shared one, moving the apps served by the previous shared interface to it
+    ///
+    /// The previous interface is kept until the windows of its apps that are open now are closed, see `Self::on_window_closed()`.
+    /// Returns the interfaces to detach right away: the previous one if none of its apps has an open window,
+    /// and the oldest replaced ones if there would be more than `MAX_SHARED_APP_INTERFACES` attached
+    fn replace_shared_interface(
+        &mut self,
+        port: u16,
+        allowed_origins: AllowedOrigins,
+        apps_windows: &HashMap<String, InstalledAppId>,
+    ) -> Vec<RetiringInterface> {
+        let mut retired = Vec::new();
+
+        if let Some(previous_port) = self.shared_port {
+            let app_ids: HashSet<InstalledAppId> = self
+                .apps_ports
+                .iter()
+                .filter(|(_, p)| **p == previous_port)
+                .map(|(app_id, _)| app_id.clone())
+                .collect();
+            for app_id in app_ids.iter() {
+                self.assign(app_id.clone(), port);
+            }
+            let window_labels: HashSet<String> = apps_windows
+                .iter()
+                .filter(|(_, app_id)| app_ids.contains(*app_id))
+                .map(|(label, _)| label.clone())
+                .collect();
+            self.allowed_origins.remove(&previous_port);
+
+            let previous = RetiringInterface {
+                port: previous_port,
+                app_ids,
+                window_labels,
+            };
+            if previous.window_labels.is_empty() {
+                retired.push(previous);
+            } else {
+                self.retiring.push(previous);
+            }
+        }
+        self.add_shared_interface(port, allowed_origins);
+
+        while self.retiring.len() + 1 > MAX_SHARED_APP_INTERFACES {
+            retired.push(self.retiring.remove(0));
+        }
+
+        retired
+    }
+
+    /// Forgets the closed window, returning the replaced interfaces that no open window may be connected through anymore
+    pub(crate) fn on_window_closed(&mut self, label: &str) -> Vec<RetiringInterface> {
+        for retiring in self.retiring.iter_mut() {
+            retiring.window_labels.remove(label);
+        }
+
+        let (retired, retiring) = std::mem::take(&mut self.retiring)
+            .into_iter()
+            .partition(|retiring| retiring.window_labels.is_empty());
+        self.retiring = retiring;
+        retired
+    }
+
+    /// Removes the given app, returning the port of its app interface if no other app is using it anymore
+    fn release(&mut self, app_id: &InstalledAppId) -> Option<u16> {
+        let port = self.apps_ports.remove(app_id)?;
+
+        if self.apps_ports.values().any(|p| *p == port) {
+            return None;
+        }
+
+        self.allowed_origins.remove(&port);
+        if self.shared_port == Some(port) {
+            self.shared_port = None;
+        }
+        Some(port)
+    }
+
+    /// Takes over the app interfaces that the conductor kept from previous runs, so that they are reused
+    ///
+    /// With `AppInterfaceStrategy::PerApp`, the interface of each installed app is kept.
+    /// With `AppInterfaceStrategy::Shared`, only the shared interface that serves the most installed apps is kept.
+    ///
+    /// Returns the ports of the interfaces that are not needed anymore, to be detached
+    pub(crate) fn adopt_persisted(
+        &mut self,
+        strategy: &AppInterfaceStrategy,
+        interfaces: Vec<AppInterfaceInfo>,
+        installed_app_ids: &[InstalledAppId],
+    ) -> Vec<u16> {
+        let mut unused_ports = Vec::new();
+
+        match strategy {
+            AppInterfaceStrategy::PerApp => {
+                for interface in interfaces {
+                    match interface.installed_app_id {
+                        Some(app_id)
+                            if installed_app_ids.contains(&app_id)
+                                && self.get_app_port(&app_id).is_none() =>
+                        {
+                            self.add_interface(interface.port, interface.allowed_origins);
+                            self.assign(app_id, interface.port);
+                        }
+                        _ => unused_ports.push(interface.port),
+                    }
+                }
+            }
+            AppInterfaceStrategy::Shared => {
+                let served_apps = |interface: &AppInterfaceInfo| -> Vec<InstalledAppId> {
+                    if interface.installed_app_id.is_some() {
+                        return vec![];
+                    }
+                    installed_app_ids
+                        .iter()
+                        .filter(|app_id| {
+                            allows_origin(
+                                &interface.allowed_origins,
+                                &happ_origin(app_id).to_string(),
+                            )
+                        })
+                        .cloned()
+                        .collect()
+                };
+                let (kept_port, shared_app_ids) = interfaces
+                    .iter()
+                    .map(|interface| (interface.port, served_apps(interface)))
+                    .filter(|(_, app_ids)| !app_ids.is_empty())
+                    .max_by_key(|(_, app_ids)| app_ids.len())
+                    .map(|(port, app_ids)| (Some(port), app_ids))
+                    .unwrap_or_default();
+
+                for interface in interfaces {
+                    if Some(interface.port) == kept_port {
+                        for app_id in shared_app_ids.iter() {
+                            self.assign(app_id.clone(), interface.port);
+                        }
+                        self.add_shared_interface(interface.port, interface.allowed_origins);
+                    } else {
+                        // Nothing is connected yet, so the apps it served can be moved to a new shared interface
+                        unused_ports.push(interface.port);
+                    }
+                }
+            }
+        }
+
+        unused_ports
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.apps_ports.clear();
+        self.allowed_origins.clear();
+        self.shared_port = None;
+        self.retiring.clear();
+    }
+}
+
+impl<R: Runtime> HolochainPlugin<R> {
+    /// Reuses the app interfaces that the conductor kept from previous runs, and detaches the ones that are not needed
+    pub(crate) async fn load_app_interfaces(&self) -> crate::Result<()> {
+        let admin_ws = self.admin_websocket().await?;
+        let interfaces = admin_ws
+            .list_app_interfaces()
+            .await
+            .map_err(|err| crate::Error::ConductorApiError(err))?;
+        let installed_app_ids: Vec<InstalledAppId> = self
+            .holochain_runtime
+            .filesystem
+            .bundle_store
+            .installed_apps_store
+            .get()?
+            .keys()
+            .cloned()
+            .collect();
+
+        let unused_ports = self
+            .holochain_runtime
+            .app_interfaces
+            .lock()
+            .await
+            .adopt_persisted(
+                &self.config.app_interface_strategy,
+                interfaces,
+                &installed_app_ids,
+            );
+
+        for port in unused_ports {
+            admin_ws
+                .detach_app_interface(port)
+                .await
+                .map_err(|err| crate::Error::ConductorApiError(err))?;
+            log::info!("Detached unused app interface at port {port}");
+        }
+
+        Ok(())
+    }
+
+    /// Returns the port of the app interface that serves the given app, attaching a new one if necessary
+    ///
+    /// With `AppInterfaceStrategy::Shared`, apps that the shared interface doesn't allow get a new shared interface
+    /// that allows all the installed apps, replacing the previous one without disconnecting the windows using it
+    pub(crate) async fn get_app_websocket_port(
+        &self,
+        admin_ws: &AdminWebsocket,
+        app_id: &InstalledAppId,
+    ) -> crate::Result<u16> {
+        let mut app_interfaces = self.holochain_runtime.app_interfaces.lock().await;
+        if let Some(app_websocket_port) = app_interfaces.get_app_port(app_id) {
+            return Ok(app_websocket_port);
+        }
+
+        let (installed_app_id, allowed_origins) = match self.config.app_interface_strategy {
+            AppInterfaceStrategy::PerApp => (
+                Some(app_id.clone()),
+                allowed_origins_for_apps(vec![app_id.clone()]),
+            ),
+            AppInterfaceStrategy::Shared => {
+                let origin = happ_origin(app_id).to_string();
+                if let Some(port) = app_interfaces.get_port_allowing_origin(&origin) {
+                    app_interfaces.assign(app_id.clone(), port);
+                    return Ok(port);
+                }
+
+                let mut app_ids: Vec<InstalledAppId> = self
+                    .holochain_runtime
+                    .filesystem
+                    .bundle_store
+                    .installed_apps_store
+                    .get()?
+                    .keys()
+                    .cloned()
+                    .collect();
+                app_ids.push(app_id.clone());
+
+                (None, allowed_origins_for_apps(app_ids))
+            }
+        };
+
+        let shared = installed_app_id.is_none();
+        let app_port = admin_ws
+            .attach_app_interface(0, allowed_origins.clone(), installed_app_id)
+            .await
+            .map_err(|err| crate::Error::ConductorApiError(err))?;
+        log::info!("Attached app interface at port {app_port}");
+
+        let retired = if shared {
+            let apps_windows = self.holochain_runtime.apps_windows.lock().await.clone();
+            app_interfaces.replace_shared_interface(app_port, allowed_origins, &apps_windows)
+        } else {
+            app_interfaces.add_interface(app_port, allowed_origins);
+            vec![]
+        };
+        app_interfaces.assign(app_id.clone(), app_port);
+
+        // The apps that were moved to the new interface need new auths pointing to it
+        self.holochain_runtime
+            .apps_websockets_auths
+            .lock()
+            .await
+            .retain(|cached_app_id, app_websocket_auth| {
+                app_interfaces.get_app_port(cached_app_id)
+                    == Some(app_websocket_auth.app_websocket_port)
+            });
+        drop(app_interfaces);
+
+        // The new interface is already serving the app
+        if let Err(err) = self.detach_retired_interfaces(admin_ws, retired).await {
+            log::error!("Failed to detach the replaced app interfaces: {err:?}");
+        }
+
+        Ok(app_port)
+    }
+
+    /// Detaches the replaced shared interfaces that are not needed anymore,
+    /// sending a new auth to the windows that were still connected through them
+    pub(crate) async fn detach_retired_interfaces(
+        &self,
+        admin_ws: &AdminWebsocket,
+        retired: Vec<RetiringInterface>,
+    ) -> crate::Result<()> {
+        for interface in retired {
+            admin_ws
+                .detach_app_interface(interface.port)
+                .await
+                .map_err(|err| crate::Error::ConductorApiError(err))?;
+            log::info!("Detached replaced app interface at port {}", interface.port);
+
+            for app_id in interface.app_ids.iter() {
+                self.drop_cached_app_websocket(app_id).await;
+            }
+
+            for label in interface.window_labels {
+                let Some(app_id) = self.get_app_id_for_window(&label).await else {
+                    continue;
+                };
+                let Some(app_websocket_port) = self.assigned_app_websocket_port(&app_id).await
+                else {
+                    continue;
+                };
+                match self
+                    .issue_app_auth_token(admin_ws, &app_id, app_websocket_port)
+                    .await
+                {
+                    Ok(app_websocket_auth) => {
+                        self.holochain_runtime
+                            .apps_websockets_auths
+                            .lock()
+                            .await
+                            .insert(app_id, app_websocket_auth.clone());
+                        self.send_app_websocket_auth_to_window(label, &app_websocket_auth);
+                    }
+                    Err(err) => {
+                        log::error!(
+                            "Failed to reissue the app auth token for app {app_id}: {err:?}"
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the port of the app interface assigned to the given app, if any
+    ///
+    /// Never attaches an app interface, the app gets one assigned when an auth token is issued for it
+    pub(crate) async fn assigned_app_websocket_port(&self, app_id: &InstalledAppId) -> Option<u16> {
+        self.holochain_runtime
+            .app_interfaces
+            .lock()
+            .await
+            .get_app_port(app_id)
+    }
+
+    /// Stops serving the given app, detaching its app interface if no other app is using it
+    pub(crate) async fn release_app_interface(
+        &self,
+        admin_ws: &AdminWebsocket,
+        app_id: &InstalledAppId,
+    ) -> crate::Result<()> {
+        let mut app_interfaces = self.holochain_runtime.app_interfaces.lock().await;
+
+        if let Some(port) = app_interfaces.release(app_id) {
+            admin_ws
+                .detach_app_interface(port)
+                .await
+                .map_err(|err| crate::Error::ConductorApiError(err))?;
+            log::info!("Detached app interface at port {port}");
+        }
+
+        Ok(())
+    }
+}
+
+fn allows_origin(allowed_origins: &AllowedOrigins, origin: &str) -> bool {
+    match allowed_origins {
+        AllowedOrigins::Any => true,
+        AllowedOrigins::Origins(origins) => origins.contains(origin),
+    }
+}
+
+fn allowed_origins_for_apps(app_ids: Vec<InstalledAppId>) -> AllowedOrigins {
+    // Allow any when the app is build in debug mode to allow normal tauri development pointing to http://localhost:1420
+    if tauri::is_dev() {
+        return AllowedOrigins::Any;
+    }
+
+    let origins: HashSet<String> = app_ids
+        .iter()
+        .map(|app_id| happ_origin(app_id).to_string())
+        .collect();
+    AllowedOrigins::Origins(origins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_ids(app_ids: &[&str]) -> Vec<InstalledAppId> {
+        app_ids.iter().map(|app_id| app_id.to_string()).collect()
+    }
+
+    fn origins(app_ids: &[&str]) -> AllowedOrigins {
+        AllowedOrigins::Origins(
+            app_ids
+                .iter()
+                .map(|app_id| happ_origin(&app_id.to_string()).to_string())
+                .collect(),
+        )
+    }
+
+    fn interface(
+        port: u16,
+        allowed_origins: AllowedOrigins,
+        installed_app_id: Option<&str>,
+    ) -> AppInterfaceInfo {
+        AppInterfaceInfo {
+            port,
+            allowed_origins,
+            installed_app_id: installed_app_id.map(String::from),
+        }
+    }
+
+    #[test]
+    fn per_app_keeps_the_interface_of_each_installed_app() {
+        let mut app_interfaces = AppInterfaces::default();
+
+        let unused_ports = app_interfaces.adopt_persisted(
+            &AppInterfaceStrategy::PerApp,
+            vec![
+                interface(1001, origins(&["forum"]), Some("forum")),
+                interface(1002, origins(&["chat"]), Some("chat")),
+                interface(1003, origins(&["uninstalled"]), Some("uninstalled")),
+                interface(1004, origins(&["forum"]), Some("forum")),
+                interface(1005, origins(&["forum", "chat"]), None),
+            ],
+            &app_ids(&["forum", "chat"]),
+        );
+
+        assert_eq!(unused_ports, vec![1003, 1004, 1005]);
+        assert_eq!(app_interfaces.get_app_port(&"forum".into()), Some(1001));
+        assert_eq!(app_interfaces.get_app_port(&"chat".into()), Some(1002));
+        assert_eq!(app_interfaces.release(&"forum".into()), Some(1001));
+    }
+
+    #[test]
+    fn shared_keeps_a_single_interface_for_all_apps() {
+        let mut app_interfaces = AppInterfaces::default();
+
+        let unused_ports = app_interfaces.adopt_persisted(
+            &AppInterfaceStrategy::Shared,
+            vec![
+                interface(1001, origins(&["forum"]), None),
+                interface(1002, origins(&["forum", "chat"]), None),
+                interface(1003, origins(&["chat"]), Some("chat")),
+                interface(1004, origins(&["uninstalled"]), None),
+                interface(1005, origins(&["files"]), Some("files")),
+            ],
+            &app_ids(&["forum", "chat", "files", "new"]),
+        );
+
+        assert_eq!(unused_ports, vec![1001, 1003, 1004, 1005]);
+        assert_eq!(app_interfaces.get_app_port(&"forum".into()), Some(1002));
+        assert_eq!(app_interfaces.get_app_port(&"chat".into()), Some(1002));
+        // Not allowed by the kept interface, they get a new shared one when they need it
+        assert_eq!(app_interfaces.get_app_port(&"files".into()), None);
+        assert_eq!(app_interfaces.get_app_port(&"new".into()), None);
+        assert_eq!(app_interfaces.shared_port, Some(1002));
+
+        // The interface is only released once no app is using it
+        assert_eq!(app_interfaces.release(&"forum".into()), None);
+        assert_eq!(app_interfaces.release(&"chat".into()), Some(1002));
+    }
+
+    #[test]
+    fn shared_reuses_the_interface_allowing_the_origin() {
+        let mut app_interfaces = AppInterfaces::default();
+        app_interfaces.add_interface(1001, origins(&["forum", "chat"]));
+
+        let port =
+            app_interfaces.get_port_allowing_origin(&happ_origin(&"chat".into()).to_string());
+        assert_eq!(port, Some(1001));
+        assert_eq!(
+            app_interfaces.get_port_allowing_origin(&happ_origin(&"new".into()).to_string()),
+            None
+        );
+    }
+
+    fn windows(windows: &[(&str, &str)]) -> HashMap<String, InstalledAppId> {
+        windows
+            .iter()
+            .map(|(label, app_id)| (label.to_string(), app_id.to_string()))
+            .collect()
+    }
+
+    fn ports(interfaces: &[RetiringInterface]) -> Vec<u16> {
+        interfaces.iter().map(|interface| interface.port).collect()
+    }
+
+    #[test]
+    fn shared_is_replaced_when_apps_are_installed() {
+        let mut app_interfaces = AppInterfaces::default();
+        app_interfaces.add_shared_interface(1001, origins(&["forum", "chat"]));
+        app_interfaces.assign("forum".into(), 1001);
+        app_interfaces.assign("chat".into(), 1001);
+
+        // Installed after the shared interface was attached, so it gets a new one allowing all the apps
+        let origin = happ_origin(&"files".into()).to_string();
+        assert_eq!(app_interfaces.get_port_allowing_origin(&origin), None);
+        let retired = app_interfaces.replace_shared_interface(
+            1002,
+            origins(&["forum", "chat", "files"]),
+            &windows(&[("forum-window", "forum"), ("other-window", "other")]),
+        );
+        app_interfaces.assign("files".into(), 1002);
+
+        // Kept until the window that was open is closed
+        assert!(retired.is_empty());
+        assert_eq!(app_interfaces.shared_port, Some(1002));
+        assert_eq!(app_interfaces.get_app_port(&"forum".into()), Some(1002));
+        assert_eq!(app_interfaces.get_app_port(&"chat".into()), Some(1002));
+        assert_eq!(app_interfaces.get_app_port(&"files".into()), Some(1002));
+        assert_eq!(app_interfaces.get_port_allowing_origin(&origin), Some(1002));
+
+        assert!(app_interfaces.on_window_closed("other-window").is_empty());
+        let retired = app_interfaces.on_window_closed("forum-window");
+        assert_eq!(ports(&retired), vec![1001]);
+        assert_eq!(
+            retired[0].app_ids,
+            HashSet::from(["forum".to_string(), "chat".to_string()])
+        );
+        assert!(retired[0].window_labels.is_empty());
+    }
+
+    #[test]
+    fn shared_is_detached_right_away_if_no_window_uses_it() {
+        let mut app_interfaces = AppInterfaces::default();
+        app_interfaces.add_shared_interface(1001, origins(&["forum"]));
+        app_interfaces.assign("forum".into(), 1001);
+
+        let retired = app_interfaces.replace_shared_interface(
+            1002,
+            origins(&["forum", "chat"]),
+            &windows(&[("chat-window", "chat")]),
+        );
+
+        assert_eq!(ports(&retired), vec![1001]);
+        assert_eq!(app_interfaces.get_app_port(&"forum".into()), Some(1002));
+        assert!(app_interfaces.on_window_closed("chat-window").is_empty());
+    }
+
+    #[test]
+    fn shared_interfaces_are_capped() {
+        let mut app_interfaces = AppInterfaces::default();
+        app_interfaces.add_shared_interface(1001, origins(&["forum"]));
+        app_interfaces.assign("forum".into(), 1001);
+        let apps_windows = windows(&[("forum-window", "forum")]);
+
+        let mut retired = vec![];
+        for port in 1002..1006 {
+            retired.extend(app_interfaces.replace_shared_interface(
+                port,
+                origins(&["forum"]),
+                &apps_windows,
+            ));
+            assert!(app_interfaces.retiring.len() + 1 <= MAX_SHARED_APP_INTERFACES);
+        }
+
+        // The oldest ones are detached even if the window may still be using them
+        assert_eq!(ports(&retired), vec![1001, 1002]);
+        assert_eq!(
+            retired[0].window_labels,
+            HashSet::from(["forum-window".to_string()])
+        );
+        assert_eq!(ports(&app_interfaces.retiring), vec![1003, 1004]);
+        assert_eq!(app_interfaces.get_app_port(&"forum".into()), Some(1005));
+
+        let retired = app_interfaces.on_window_closed("forum-window");
+        assert_eq!(ports(&retired), vec![1003, 1004]);
+        assert!(app_interfaces.retiring.is_empty());
+    }
+
+    #[test]
+    fn shared_is_released_once_no_app_uses_it() {
+        let mut app_interfaces = AppInterfaces::default();
+        app_interfaces.add_shared_interface(1001, origins(&["forum", "chat"]));
+        app_interfaces.assign("forum".into(), 1001);
+        app_interfaces.assign("chat".into(), 1001);
+
+        assert_eq!(app_interfaces.release(&"forum".into()), None);
+        assert_eq!(app_interfaces.release(&"chat".into()), Some(1001));
+        assert_eq!(app_interfaces.shared_port, None);
+    }
+}
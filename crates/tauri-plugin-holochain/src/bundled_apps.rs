@@ -0,0 +1,379 @@
+use std::{borrow::Cow, collections::HashMap, future::Future};
+
+use holochain::prelude::{AppBundle, MembraneProof, NetworkSeed, RoleName};
+use holochain_client::InstalledAppId;
+use holochain_types::web_app::WebAppBundle;
+use serde::Serialize;
+use tauri::{Manager, Runtime};
+
+use crate::{
+    filesystem::{AppBundleStore, BundleStore, InstalledAppInfo, JsonStore},
+    HolochainPlugin, InstallOptions,
+};
+
+/// The hash of the bundle that each bundled app was last installed or updated from
+pub(crate) type BundledAppsStore = JsonStore<HashMap<InstalledAppId, String>>;
+
+/// Event emitted when a bundled app could not be installed or updated, with a `BundledAppFailedPayload`
+pub const BUNDLED_APP_FAILED_EVENT: &'static str = "bundled-app-failed";
+
+/// Payload of the `bundled-app-failed` event
+#[derive(Serialize, Debug, Clone)]
+pub struct BundledAppFailedPayload {
+    pub app_id: InstalledAppId,
+    pub error: String,
+}
+
+/// An app shipped inside the binary, which the plugin installs when it's first launched
+/// and updates whenever the bundle changes, before emitting `holochain-ready`
+///
+/// Apps that were updated from somewhere else, e.g. with an update channel, are not reverted to the bundled app
+#[derive(Debug, Clone)]
+pub struct BundledApp {
+    pub app_id: InstalledAppId,
+    pub bundle: BundledAppBytes,
+    pub network_seed: Option<NetworkSeed>,
+    /// Membrane proofs for the roles that need them when the app is installed
+    pub membrane_proofs: HashMap<RoleName, MembraneProof>,
+    /// Hex-encoded ed25519 signature by the publisher over the hash of the web-hApp bundle,
+    /// required for web-hApps if `HolochainPluginConfig::trusted_publishers` is not empty
+    pub publisher_signature: Option<String>,
+}
+
+/// The encoded bundle of a `BundledApp`, e.g. `Cow::Borrowed(include_bytes!(...))` to not copy it
+#[derive(Debug, Clone)]
+pub enum BundledAppBytes {
+    /// A `.happ` file, installed with `HolochainPlugin::install_app()`
+    Happ(Cow<'static, [u8]>),
+    /// A `.webhapp` file, installed with `HolochainPlugin::install_web_app()`
+    WebHapp(Cow<'static, [u8]>),
+}
+
+impl BundledApp {
+    /// A bundled app without network seed or membrane proofs
+    ///
+    /// * `app_id` - the app id to install the app with
+    /// * `bundle` - the encoded `.happ` or `.webhapp`
+    pub fn new(app_id: InstalledAppId, bundle: BundledAppBytes) -> Self {
+        BundledApp {
+            app_id,
+            bundle,
+            network_seed: None,
+            membrane_proofs: HashMap::new(),
+            publisher_signature: None,
+        }
+    }
+}
+
+/// What to do with a bundled app when the plugin is launched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BundledAppAction {
+    Install,
+    Update,
+    Skip,
+}
+
+/// Decides whether to install or update a bundled app
+///
+/// An installed app is only updated if it's still running the previously bundled app,
+/// so that apps updated from somewhere else don't get reverted
+///
+/// * `installed` - whether the app is installed in the conductor
+/// * `installed_hash` - the hash of the bundle the app is running, if it's known
+/// * `previously_bundled_hash` - the hash of the bundle it was last installed or updated from as a bundled app
+fn bundled_app_action(
+    bundled_hash: &str,
+    installed: bool,
+    installed_hash: Option<&str>,
+    previously_bundled_hash: Option<&str>,
+) -> BundledAppAction {
+    if !installed {
+        return BundledAppAction::Install;
+    }
+    match (installed_hash, previously_bundled_hash) {
+        (Some(installed_hash), _) if installed_hash.eq(bundled_hash) => BundledAppAction::Skip,
+        (Some(installed_hash), Some(previously_bundled_hash))
+            if installed_hash.eq(previously_bundled_hash) =>
+        {
+            BundledAppAction::Update
+        }
+        _ => BundledAppAction::Skip,
+    }
+}
+
+/// Runs `handle` for each of the bundled apps, returning the errors of the ones that failed
+/// instead of stopping at the first one
+async fn handle_each_bundled_app<F, Fut>(
+    bundled_apps: Vec<BundledApp>,
+    mut handle: F,
+) -> Vec<(InstalledAppId, crate::Error)>
+where
+    F: FnMut(BundledApp) -> Fut,
+    Fut: Future<Output = crate::Result<()>>,
+{
+    let mut failures = vec![];
+    for bundled_app in bundled_apps {
+        let app_id = bundled_app.app_id.clone();
+        if let Err(err) = handle(bundled_app).await {
+            failures.push((app_id, err));
+        }
+    }
+    failures
+}
+
+/// The decoded bundle of a `BundledApp`
+enum DecodedBundle {
+    Happ(AppBundle),
+    WebHapp(WebAppBundle),
+}
+
+impl DecodedBundle {
+    fn decode(bytes: &BundledAppBytes) -> crate::Result<Self> {
+        Ok(match bytes {
+            BundledAppBytes::Happ(bytes) => DecodedBundle::Happ(AppBundle::decode(bytes)?),
+            BundledAppBytes::WebHapp(bytes) => DecodedBundle::WebHapp(WebAppBundle::decode(bytes)?),
+        })
+    }
+
+    fn hash(&self) -> crate::Result<String> {
+        match self {
+            DecodedBundle::Happ(app_bundle) => AppBundleStore::app_bundle_hash(app_bundle),
+            DecodedBundle::WebHapp(web_app_bundle) => {
+                BundleStore::web_app_bundle_hash(web_app_bundle)
+            }
+        }
+    }
+
+    /// The hash of the same kind of bundle that the given installed app is running
+    fn installed_hash(&self, installed_app_info: &InstalledAppInfo) -> Option<String> {
+        match self {
+            DecodedBundle::Happ(_) => Some(installed_app_info.happ_bundle_hash.clone()),
+            DecodedBundle::WebHapp(_) => installed_app_info
+                .web_app_info
+                .as_ref()
+                .map(|web_app_info| web_app_info.web_happ_bundle_hash.clone()),
+        }
+    }
+}
+
+impl<R: Runtime> HolochainPlugin<R> {
+    /// Installs the given bundled apps if they are not installed yet,
+    /// or updates them if their bundle changed since they were installed or last updated as bundled apps
+    ///
+    /// An app that fails to be installed or updated doesn't stop the others,
+    /// its error is logged and emitted with the `bundled-app-failed` event instead
+    pub(crate) async fn install_or_update_bundled_apps(
+        &self,
+        bundled_apps: Vec<BundledApp>,
+    ) -> crate::Result<()> {
+        if bundled_apps.is_empty() {
+            return Ok(());
+        }
+
+        let admin_ws = self.admin_websocket().await?;
+        let installed_apps = admin_ws
+            .list_apps(None)
+            .await
+            .map_err(|err| crate::Error::ConductorApiError(err))?;
+
+        let failures = handle_each_bundled_app(bundled_apps, |bundled_app| {
+            let installed = installed_apps
+                .iter()
+                .any(|app| app.installed_app_id.eq(&bundled_app.app_id));
+            self.install_or_update_bundled_app(bundled_app, installed)
+        })
+        .await;
+
+        for (app_id, err) in failures {
+            log::error!("Failed to install or update bundled app {app_id}: {err:?}");
+            self.app_handle.emit(
+                BUNDLED_APP_FAILED_EVENT,
+                BundledAppFailedPayload {
+                    app_id,
+                    error: err.to_string(),
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+
+    async fn install_or_update_bundled_app(
+        &self,
+        bundled_app: BundledApp,
+        installed: bool,
+    ) -> crate::Result<()> {
+        let bundle_store = &self.holochain_runtime.filesystem.bundle_store;
+        let app_id = bundled_app.app_id.clone();
+        let bundle = DecodedBundle::decode(&bundled_app.bundle)?;
+        let bundled_hash = bundle.hash()?;
+
+        let installed_hash = bundle_store
+            .installed_apps_store
+            .get()?
+            .get(&app_id)
+            .and_then(|installed_app_info| bundle.installed_hash(installed_app_info));
+        let previously_bundled_hash = bundle_store.bundled_apps_store.get()?.get(&app_id).cloned();
+
+        match bundled_app_action(
+            &bundled_hash,
+            installed,
+            installed_hash.as_deref(),
+            previously_bundled_hash.as_deref(),
+        ) {
+            BundledAppAction::Install => {
+                log::info!("Installing bundled app {app_id}");
+                self.install_bundled_app(bundled_app, bundle).await?;
+            }
+            BundledAppAction::Update => {
+                log::info!("Updating bundled app {app_id}");
+                self.update_bundled_app(bundled_app, bundle).await?;
+            }
+            BundledAppAction::Skip => {
+                if !installed_hash.as_deref().eq(&Some(bundled_hash.as_str())) {
+                    log::info!(
+                        "Not updating bundled app {app_id}, since it was updated from somewhere else"
+                    );
+                }
+                return Ok(());
+            }
+        }
+
+        bundle_store.bundled_apps_store.update(|bundled_apps| {
+            bundled_apps.insert(app_id.clone(), bundled_hash.clone());
+        })
+    }
+
+    async fn install_bundled_app(
+        &self,
+        bundled_app: BundledApp,
+        bundle: DecodedBundle,
+    ) -> crate::Result<()> {
+        let options = InstallOptions {
+            network_seed: bundled_app.network_seed,
+            publisher_signature: bundled_app.publisher_signature,
+            ..Default::default()
+        };
+        match bundle {
+            DecodedBundle::Happ(app_bundle) => {
+                self.install_app(
+                    bundled_app.app_id,
+                    app_bundle,
+                    bundled_app.membrane_proofs,
+                    options,
+                )
+                .await?;
+            }
+            DecodedBundle::WebHapp(web_app_bundle) => {
+                self.install_web_app(
+                    bundled_app.app_id,
+                    web_app_bundle,
+                    bundled_app.membrane_proofs,
+                    options,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn update_bundled_app(
+        &self,
+        bundled_app: BundledApp,
+        bundle: DecodedBundle,
+    ) -> crate::Result<()> {
+        match bundle {
+            DecodedBundle::Happ(app_bundle) => {
+                self.update_app(bundled_app.app_id, app_bundle).await?;
+            }
+            DecodedBundle::WebHapp(web_app_bundle) => {
+                self.update_signed_web_app(
+                    bundled_app.app_id,
+                    web_app_bundle,
+                    bundled_app.publisher_signature,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_apps_are_installed_or_updated_only_from_the_previous_bundle() {
+        // Not installed yet
+        assert_eq!(
+            bundled_app_action("v2", false, None, None),
+            BundledAppAction::Install
+        );
+        // Removed by the user after being installed as a bundled app
+        assert_eq!(
+            bundled_app_action("v2", false, None, Some("v1")),
+            BundledAppAction::Install
+        );
+        // Already running the bundled app
+        assert_eq!(
+            bundled_app_action("v2", true, Some("v2"), Some("v2")),
+            BundledAppAction::Skip
+        );
+        assert_eq!(
+            bundled_app_action("v2", true, Some("v2"), None),
+            BundledAppAction::Skip
+        );
+        // Still running the previously bundled app
+        assert_eq!(
+            bundled_app_action("v2", true, Some("v1"), Some("v1")),
+            BundledAppAction::Update
+        );
+        // Updated from somewhere else, e.g. an update channel
+        assert_eq!(
+            bundled_app_action("v2", true, Some("v3"), Some("v1")),
+            BundledAppAction::Skip
+        );
+        // Installed from somewhere else before being bundled
+        assert_eq!(
+            bundled_app_action("v2", true, Some("v1"), None),
+            BundledAppAction::Skip
+        );
+        assert_eq!(
+            bundled_app_action("v2", true, None, Some("v1")),
+            BundledAppAction::Skip
+        );
+    }
+
+    #[test]
+    fn a_failing_bundled_app_does_not_stop_the_others() {
+        let bundled_apps = ["forum", "chat", "files"]
+            .into_iter()
+            .map(|app_id| {
+                BundledApp::new(
+                    InstalledAppId::from(app_id),
+                    BundledAppBytes::Happ(Cow::Borrowed(&[])),
+                )
+            })
+            .collect();
+
+        let mut handled = vec![];
+        let failures =
+            tauri::async_runtime::block_on(handle_each_bundled_app(bundled_apps, |bundled_app| {
+                handled.push(bundled_app.app_id.clone());
+                let fails = bundled_app.app_id.eq("chat");
+                async move {
+                    if fails {
+                        return Err(crate::Error::AppDoesNotExist(bundled_app.app_id));
+                    }
+                    Ok(())
+                }
+            }));
+
+        assert_eq!(handled, vec!["forum", "chat", "files"]);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, "chat");
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use holochain::prelude::{
+    CloneCellId, ClonedCell, CreateCloneCellPayload, DeleteCloneCellPayload,
+    DisableCloneCellPayload, EnableCloneCellPayload,
+};
+use holochain_client::{ConductorApiError, InstalledAppId};
+use serde::Serialize;
+use tauri::{EventTarget, Manager, Runtime};
+
+use crate::HolochainPlugin;
+
+/// Event emitted to the windows of an app when a clone cell is created for it
+pub const CLONE_CELL_CREATED_EVENT: &'static str = "clone-cell-created";
+/// Event emitted to the windows of an app when one of its disabled clone cells is enabled again
+pub const CLONE_CELL_ENABLED_EVENT: &'static str = "clone-cell-enabled";
+/// Event emitted to the windows of an app when one of its clone cells is disabled
+pub const CLONE_CELL_DISABLED_EVENT: &'static str = "clone-cell-disabled";
+/// Event emitted to the windows of an app when one of its disabled clone cells is deleted
+pub const CLONE_CELL_DELETED_EVENT: &'static str = "clone-cell-deleted";
+
+/// Payload of the `clone-cell-created` and `clone-cell-enabled` events
+#[derive(Serialize, Debug, Clone)]
+pub struct ClonedCellPayload {
+    pub app_id: InstalledAppId,
+    pub cloned_cell: ClonedCell,
+}
+
+/// Payload of the `clone-cell-disabled` and `clone-cell-deleted` events
+#[derive(Serialize, Debug, Clone)]
+pub struct CloneCellIdPayload {
+    pub app_id: InstalledAppId,
+    pub clone_cell_id: CloneCellId,
+}
+
+impl<R: Runtime> HolochainPlugin<R> {
+    /// Creates a clone cell for the given app
+    ///
+    /// * `app_id` - the app to create the clone cell for
+    /// * `payload` - the role to clone, and the modifiers, membrane proof and name for the clone
+    pub async fn create_clone_cell(
+        &self,
+        app_id: InstalledAppId,
+        payload: CreateCloneCellPayload,
+    ) -> crate::Result<ClonedCell> {
+        let app_ws = self.cached_app_websocket(&app_id).await?;
+        let result = app_ws.create_clone_cell(payload).await;
+        let cloned_cell = self.handle_app_ws_result(&app_id, result).await?;
+
+        log::info!(
+            "Created clone cell {} for app {app_id}",
+            cloned_cell.clone_id
+        );
+        self.emit_to_app_windows(
+            &app_id,
+            CLONE_CELL_CREATED_EVENT,
+            ClonedCellPayload {
+                app_id: app_id.clone(),
+                cloned_cell: cloned_cell.clone(),
+            },
+        )
+        .await?;
+
+        Ok(cloned_cell)
+    }
+
+    /// Enables a disabled clone cell of the given app
+    ///
+    /// * `app_id` - the app the clone cell belongs to
+    /// * `clone_cell_id` - the clone cell to enable
+    pub async fn enable_clone_cell(
+        &self,
+        app_id: InstalledAppId,
+        clone_cell_id: CloneCellId,
+    ) -> crate::Result<ClonedCell> {
+        let app_ws = self.cached_app_websocket(&app_id).await?;
+        let result = app_ws
+            .enable_clone_cell(EnableCloneCellPayload { clone_cell_id })
+            .await;
+        let cloned_cell = self.handle_app_ws_result(&app_id, result).await?;
+
+        log::info!(
+            "Enabled clone cell {} for app {app_id}",
+            cloned_cell.clone_id
+        );
+        self.emit_to_app_windows(
+            &app_id,
+            CLONE_CELL_ENABLED_EVENT,
+            ClonedCellPayload {
+                app_id: app_id.clone(),
+                cloned_cell: cloned_cell.clone(),
+            },
+        )
+        .await?;
+
+        Ok(cloned_cell)
+    }
+
+    /// Disables a clone cell of the given app, which can be enabled again or deleted afterwards
+    ///
+    /// * `app_id` - the app the clone cell belongs to
+    /// * `clone_cell_id` - the clone cell to disable
+    pub async fn disable_clone_cell(
+        &self,
+        app_id: InstalledAppId,
+        clone_cell_id: CloneCellId,
+    ) -> crate::Result<()> {
+        let app_ws = self.cached_app_websocket(&app_id).await?;
+        let result = app_ws
+            .disable_clone_cell(DisableCloneCellPayload {
+                clone_cell_id: clone_cell_id.clone(),
+            })
+            .await;
+        self.handle_app_ws_result(&app_id, result).await?;
+
+        log::info!("Disabled clone cell {clone_cell_id:?} for app {app_id}");
+        self.emit_to_app_windows(
+            &app_id,
+            CLONE_CELL_DISABLED_EVENT,
+            CloneCellIdPayload {
+                app_id: app_id.clone(),
+                clone_cell_id,
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Deletes a disabled clone cell of the given app, along with all its data
+    ///
+    /// * `app_id` - the app the clone cell belongs to
+    /// * `clone_cell_id` - the clone cell to delete, which must have been disabled before
+    pub async fn delete_clone_cell(
+        &self,
+        app_id: InstalledAppId,
+        clone_cell_id: CloneCellId,
+    ) -> crate::Result<()> {
+        let admin_ws = self.admin_websocket().await?;
+        admin_ws
+            .delete_clone_cell(DeleteCloneCellPayload {
+                app_id: app_id.clone(),
+                clone_cell_id: clone_cell_id.clone(),
+            })
+            .await
+            .map_err(|err| crate::Error::ConductorApiError(err))?;
+
+        log::info!("Deleted clone cell {clone_cell_id:?} for app {app_id}");
+        self.emit_to_app_windows(
+            &app_id,
+            CLONE_CELL_DELETED_EVENT,
+            CloneCellIdPayload {
+                app_id: app_id.clone(),
+                clone_cell_id,
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Emits the given event only to the windows of the given app, like the `holochain://signal` event
+    async fn emit_to_app_windows<S: Serialize + Clone>(
+        &self,
+        app_id: &InstalledAppId,
+        event: &str,
+        payload: S,
+    ) -> crate::Result<()> {
+        let apps_windows = self.holochain_runtime.apps_windows.lock().await.clone();
+        emit_to_windows_of_app(&apps_windows, app_id, payload, |label, payload| {
+            self.app_handle
+                .emit_to(EventTarget::AnyLabel { label }, event, payload)
+        })
+    }
+
+    /// Maps the result of a request made with the cached `AppWebsocket` of the app
+    async fn handle_app_ws_result<T>(
+        &self,
+        app_id: &InstalledAppId,
+        result: Result<T, ConductorApiError>,
+    ) -> crate::Result<T> {
+        match result {
+            Ok(value) => Ok(value),
+            Err(err) => Err(self.handle_app_websocket_error(app_id, err).await),
+        }
+    }
+}
+
+/// Calls `emit` with the payload for each of the windows that belong to the given app
+fn emit_to_windows_of_app<S: Clone>(
+    apps_windows: &HashMap<String, InstalledAppId>,
+    app_id: &InstalledAppId,
+    payload: S,
+    mut emit: impl FnMut(String, S) -> tauri::Result<()>,
+) -> crate::Result<()> {
+    for (label, window_app_id) in apps_windows {
+        if window_app_id.eq(app_id) {
+            emit(label.clone(), payload.clone())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use holochain::prelude::CloneId;
+
+    use super::*;
+
+    fn apps_windows() -> HashMap<String, InstalledAppId> {
+        HashMap::from([
+            (String::from("forum"), InstalledAppId::from("forum")),
+            (
+                String::from("forum-settings"),
+                InstalledAppId::from("forum"),
+            ),
+            (String::from("chat"), InstalledAppId::from("chat")),
+        ])
+    }
+
+    fn emitted_to(app_id: &str, payload: CloneCellIdPayload) -> Vec<(String, CloneCellIdPayload)> {
+        let mut emitted = vec![];
+        emit_to_windows_of_app(
+            &apps_windows(),
+            &InstalledAppId::from(app_id),
+            payload,
+            |label, payload| {
+                emitted.push((label, payload));
+                Ok(())
+            },
+        )
+        .unwrap();
+        emitted.sort_by(|a, b| a.0.cmp(&b.0));
+        emitted
+    }
+
+    #[test]
+    fn events_are_only_emitted_to_the_windows_of_the_app() {
+        let payload = CloneCellIdPayload {
+            app_id: InstalledAppId::from("forum"),
+            clone_cell_id: CloneCellId::CloneId(CloneId(String::from("forum.0"))),
+        };
+
+        let emitted = emitted_to("forum", payload.clone());
+
+        let labels: Vec<&str> = emitted.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, vec!["forum", "forum-settings"]);
+        for (_, emitted_payload) in emitted {
+            assert_eq!(emitted_payload.app_id, payload.app_id);
+            assert_eq!(emitted_payload.clone_cell_id, payload.clone_cell_id);
+        }
+    }
+
+    #[test]
+    fn events_for_apps_without_windows_are_not_emitted() {
+        let payload = CloneCellIdPayload {
+            app_id: InstalledAppId::from("files"),
+            clone_cell_id: CloneCellId::CloneId(CloneId(String::from("files.0"))),
+        };
+
+        assert!(emitted_to("files", payload).is_empty());
+    }
+}
@@ -1,5 +1,11 @@
+pub mod call_zome;
+pub mod clone_cells;
+pub mod get_app_auth_token;
 pub mod get_runtime_info;
 pub mod install_web_app;
+pub mod list_agents;
 pub mod list_apps;
+pub mod membrane_proofs;
+pub mod notifications;
 pub mod open_app;
 pub mod sign_zome_call;
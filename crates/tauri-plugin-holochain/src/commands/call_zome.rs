@@ -0,0 +1,44 @@
+use holochain::prelude::{CellId, ExternIO, FunctionName, ZomeName};
+use holochain_client::ZomeCallTarget;
+use serde_json::Value;
+use tauri::{command, AppHandle, Runtime, Window};
+
+use crate::{commands::sign_zome_call::authorize_zome_call, json_value::JsonValue, HolochainExt};
+
+/// Calls the given zome function from the Rust side, taking care of the nonce generation and the signing
+///
+/// The payload is msgpack-encoded from its JSON representation, and the result is decoded back to JSON,
+/// with byte arrays (e.g. hashes) decoded as arrays of numbers
+#[command]
+pub(crate) async fn call_zome<R: Runtime>(
+    app_handle: AppHandle<R>,
+    window: Window<R>,
+    cell_id: CellId,
+    zome_name: ZomeName,
+    fn_name: FunctionName,
+    payload: Value,
+) -> crate::Result<Value> {
+    let holochain = app_handle.holochain()?;
+    let app_id =
+        authorize_zome_call(holochain, window.label(), cell_id.agent_pubkey(), &cell_id).await?;
+
+    let app_ws = holochain.cached_app_websocket(&app_id).await?;
+
+    let result = app_ws
+        .call_zome(
+            ZomeCallTarget::CellId(cell_id),
+            zome_name,
+            fn_name,
+            ExternIO::encode(payload)?,
+        )
+        .await;
+
+    let result = match result {
+        Ok(result) => result,
+        Err(err) => return Err(holochain.handle_app_websocket_error(&app_id, err).await),
+    };
+
+    let JsonValue(value) = result.decode()?;
+
+    Ok(value)
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use holochain::prelude::{CloneCellId, ClonedCell, CreateCloneCellPayload};
+use holochain_client::InstalledAppId;
+use tauri::{command, AppHandle, Runtime, Window};
+
+use crate::{HolochainExt, HolochainPlugin};
+
+/// Clone cells can only be managed by the windows of the app they belong to
+async fn app_id_for_window<R: Runtime>(
+    holochain: &HolochainPlugin<R>,
+    window: &Window<R>,
+) -> crate::Result<InstalledAppId> {
+    let apps_windows = holochain.holochain_runtime.apps_windows.lock().await;
+    window_app_id(&apps_windows, window.label())
+}
+
+/// The app the window with the given label belongs to, which is the only app whose clone cells it can manage
+fn window_app_id(
+    apps_windows: &HashMap<String, InstalledAppId>,
+    label: &str,
+) -> crate::Result<InstalledAppId> {
+    apps_windows
+        .get(label)
+        .cloned()
+        .ok_or(crate::Error::WindowWithoutApp(label.to_string()))
+}
+
+#[command]
+pub(crate) async fn create_clone_cell<R: Runtime>(
+    app_handle: AppHandle<R>,
+    window: Window<R>,
+    payload: CreateCloneCellPayload,
+) -> crate::Result<ClonedCell> {
+    let holochain = app_handle.holochain()?;
+    let app_id = app_id_for_window(holochain, &window).await?;
+    holochain.create_clone_cell(app_id, payload).await
+}
+
+#[command]
+pub(crate) async fn enable_clone_cell<R: Runtime>(
+    app_handle: AppHandle<R>,
+    window: Window<R>,
+    clone_cell_id: CloneCellId,
+) -> crate::Result<ClonedCell> {
+    let holochain = app_handle.holochain()?;
+    let app_id = app_id_for_window(holochain, &window).await?;
+    holochain.enable_clone_cell(app_id, clone_cell_id).await
+}
+
+#[command]
+pub(crate) async fn disable_clone_cell<R: Runtime>(
+    app_handle: AppHandle<R>,
+    window: Window<R>,
+    clone_cell_id: CloneCellId,
+) -> crate::Result<()> {
+    let holochain = app_handle.holochain()?;
+    let app_id = app_id_for_window(holochain, &window).await?;
+    holochain.disable_clone_cell(app_id, clone_cell_id).await
+}
+
+#[command]
+pub(crate) async fn delete_clone_cell<R: Runtime>(
+    app_handle: AppHandle<R>,
+    window: Window<R>,
+    clone_cell_id: CloneCellId,
+) -> crate::Result<()> {
+    let holochain = app_handle.holochain()?;
+    let app_id = app_id_for_window(holochain, &window).await?;
+    holochain.delete_clone_cell(app_id, clone_cell_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_manage_the_clone_cells_of_their_own_app() {
+        let apps_windows = HashMap::from([
+            (String::from("forum"), InstalledAppId::from("forum")),
+            (String::from("main"), InstalledAppId::from("chat")),
+        ]);
+
+        assert_eq!(
+            window_app_id(&apps_windows, "forum").unwrap(),
+            InstalledAppId::from("forum")
+        );
+        // The label of a window doesn't need to be the id of its app
+        assert_eq!(
+            window_app_id(&apps_windows, "main").unwrap(),
+            InstalledAppId::from("chat")
+        );
+        assert!(matches!(
+            window_app_id(&apps_windows, "chat"),
+            Err(crate::Error::WindowWithoutApp(label)) if label == "chat"
+        ));
+    }
+}
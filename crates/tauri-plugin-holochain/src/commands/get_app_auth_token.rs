@@ -0,0 +1,18 @@
+use tauri::{command, AppHandle, Runtime, Window};
+
+use crate::{AppWebsocketAuth, HolochainExt};
+
+/// Issues a new auth token for the app websocket of the app the calling window belongs to
+#[command]
+pub(crate) async fn get_app_auth_token<R: Runtime>(
+    app_handle: AppHandle<R>,
+    window: Window<R>,
+) -> crate::Result<AppWebsocketAuth> {
+    let holochain = app_handle.holochain()?;
+
+    let Some(app_id) = holochain.get_app_id_for_window(window.label()).await else {
+        return Err(crate::Error::WindowWithoutApp(window.label().to_string()));
+    };
+
+    holochain.refresh_app_websocket_auth(&app_id).await
+}
@@ -1,56 +1,32 @@
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     path::PathBuf,
 };
 
 use holochain::prelude::{
-    AppBundle, AppBundleError, AppBundleSource, AppManifest, CoordinatorBundle,
-    CoordinatorManifest, DnaBundle, DnaError, DnaFile, DnaHash, MembraneProof, NetworkSeed,
-    RoleName, UpdateCoordinatorsPayload, ZomeDependency, ZomeError, ZomeLocation, ZomeManifest,
+    AppBundle, AppBundleError, AppBundleSource, AppManifest, CloneId, CoordinatorBundle,
+    CoordinatorManifest, CoordinatorZomeDef, DnaBundle, DnaDef, DnaError, DnaFile, DnaHash,
+    MembraneProof, NetworkSeed, RoleName, UpdateCoordinatorsPayload, WasmHash, ZomeDependency,
+    ZomeError, ZomeLocation, ZomeManifest, ZomeName,
 };
 use holochain_client::{
-    AdminWebsocket, AppInfo, ConductorApiError, InstallAppPayload, InstalledAppId,
+    AdminWebsocket, AgentPubKey, AppInfo, ConductorApiError, InstallAppPayload, InstalledAppId,
 };
 use holochain_conductor_api::{AppInfoStatus, CellInfo};
-use holochain_types::web_app::WebAppBundle;
 use mr_bundle::{error::MrBundleError, Bundle, ResourceBytes};
+use serde::Serialize;
 
 use crate::filesystem::FileSystemError;
 
-pub async fn install_web_app(
-    admin_ws: &AdminWebsocket,
-    app_id: String,
-    bundle: WebAppBundle,
-    membrane_proofs: HashMap<RoleName, MembraneProof>,
-    network_seed: Option<NetworkSeed>,
-) -> crate::Result<AppInfo> {
-    let app_info = install_app(
-        admin_ws,
-        app_id.clone(),
-        bundle.happ_bundle().await?,
-        membrane_proofs,
-        network_seed,
-    )
-    .await?;
-
-    log::info!("Installed web-app's ui {app_id:?}");
-
-    Ok(app_info)
-}
-
 pub async fn install_app(
     admin_ws: &AdminWebsocket,
     app_id: String,
     bundle: AppBundle,
+    agent_key: AgentPubKey,
     membrane_proofs: HashMap<RoleName, MembraneProof>,
     network_seed: Option<NetworkSeed>,
 ) -> crate::Result<AppInfo> {
-    log::info!("Installing app {}", app_id);
-
-    let agent_key = admin_ws
-        .generate_agent_pub_key()
-        .await
-        .map_err(|err| crate::Error::ConductorApiError(err))?;
+    log::info!("Installing app {} for agent {}", app_id, agent_key);
 
     let app_info = admin_ws
         .install_app(InstallAppPayload {
@@ -105,13 +81,135 @@ pub enum UpdateAppError {
 
     #[error("The role {0} was not found the app {1}")]
     RoleNotFound(RoleName, InstalledAppId),
+
+    #[error("The integrity zomes for role {role} changed (installed DNA {old_dna}, new DNA {new_dna}), the app needs to be migrated to a new app instead")]
+    IntegrityChanged {
+        role: RoleName,
+        old_dna: DnaHash,
+        new_dna: DnaHash,
+    },
+
+    #[error("Error accessing the stored bundles: {0}")]
+    BundleStoreError(String),
+
+    #[error("Updating the app failed and it was rolled back: {error}")]
+    UpdateRolledBack {
+        error: String,
+        cells: Vec<CellUpdateResult>,
+    },
+}
+
+/// Outcome of updating the coordinator zomes of one of the cells of an app
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", content = "error", rename_all = "snake_case")]
+pub enum CellUpdateOutcome {
+    /// Its coordinator zomes were already up to date
+    Unchanged,
+    /// Its coordinator zomes were updated
+    Updated,
+    /// Its coordinator zomes were updated, but restored to the previous ones because the update of the app failed
+    RolledBack,
+    /// Its coordinator zomes were updated and the update of the app failed, but the zomes that were added
+    /// stay installed since the conductor can't remove coordinator zomes. Any replaced zomes were restored
+    NotRolledBack,
+    /// Updating its coordinator zomes failed
+    Failed(String),
+    /// Its coordinator zomes were updated, but could not be restored after the update of the app failed
+    RollbackFailed(String),
+    /// Its coordinator zomes were not updated because the update of a previous cell failed
+    Skipped,
+}
+
+/// Result of updating the coordinator zomes of one of the cells of an app
+#[derive(Serialize, Debug, Clone)]
+pub struct CellUpdateResult {
+    pub role_name: RoleName,
+    /// The clone id if the cell is a clone cell
+    pub clone_id: Option<CloneId>,
+    pub dna_hash: DnaHash,
+    pub outcome: CellUpdateOutcome,
+}
+
+/// How a coordinator zome in the new version of a DNA compares with the installed one
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CoordinatorZomeChange {
+    /// The zome is not in the installed DNA
+    Added,
+    /// The zome is in the installed DNA with a different wasm
+    Replaced,
+    Unchanged,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CoordinatorZomePlan {
+    pub zome_name: ZomeName,
+    pub change: CoordinatorZomeChange,
+}
+
+/// What would change in one of the cells of an app when updating it
+#[derive(Serialize, Debug, Clone)]
+pub struct CellUpdatePlan {
+    pub role_name: RoleName,
+    /// The clone id if the cell is a clone cell
+    pub clone_id: Option<CloneId>,
+    /// The DNA hash of the installed cell
+    pub dna_hash: DnaHash,
+    /// The DNA hash of the new version of the DNA, with the modifiers in the new bundle
+    pub new_dna_hash: DnaHash,
+    /// Whether the integrity zomes changed, in which case the app can't be updated in place
+    pub integrity_changed: bool,
+    /// The coordinator zomes in the new version of the DNA
+    pub coordinator_zomes: Vec<CoordinatorZomePlan>,
 }
 
+/// What would change in an app when updating it to a new bundle
+#[derive(Serialize, Debug, Clone)]
+pub struct UpdatePlan {
+    pub app_id: InstalledAppId,
+    pub cells: Vec<CellUpdatePlan>,
+    /// Whether the integrity zomes of any of the cells changed, in which case the app can't be updated in place
+    pub integrity_changed: bool,
+    /// Whether the UI changed, `None` if the new bundle or the installed app don't have a UI
+    pub ui_changed: Option<bool>,
+}
+
+/// The update to apply to a cell, computed before changing anything in the conductor
+struct CellUpdate {
+    role_name: RoleName,
+    clone_id: Option<CloneId>,
+    dna_hash: DnaHash,
+    /// The new coordinator zomes for the cell, `None` if they are already up to date
+    new_coordinators: Option<CoordinatorBundle>,
+    /// Whether any of the new coordinator zomes is not in the installed DNA, which can't be undone
+    adds_zomes: bool,
+    /// How to roll back the new coordinator zomes if the update fails
+    rollback: Rollback,
+}
+
+enum Rollback {
+    /// Only new coordinator zomes are added, which can't be removed
+    Nothing,
+    /// Restore the previous version of the coordinator zomes that are replaced
+    Restore(CoordinatorBundle),
+    /// The previous version of some of the replaced coordinator zomes is not available
+    Unavailable,
+}
+
+/// Updates the coordinator zomes of all the cells of the given app, including its clone cells
+///
+/// All the new DNAs are resolved and diffed against the installed ones before changing anything.
+/// If updating any of the cells fails, the cells that were already updated are rolled back to the coordinator zomes
+/// in `previous_bundle` and the app is enabled again, returning `UpdateAppError::UpdateRolledBack` with the result for each cell.
+/// Coordinator zomes that were added can't be removed, those cells are reported as `CellUpdateOutcome::NotRolledBack`.
+///
+/// * `previous_bundle` - the bundle the app was installed or last updated with, needed to roll back
 pub async fn update_app(
     admin_ws: &AdminWebsocket,
     app_id: String,
     bundle: AppBundle,
-) -> Result<(), UpdateAppError> {
+    previous_bundle: Option<AppBundle>,
+) -> Result<Vec<CellUpdateResult>, UpdateAppError> {
     log::info!(
         "Checking whether the coordinator zomes for app {} need to be updated",
         app_id
@@ -123,118 +221,421 @@ pub async fn update_app(
         .await
         .map_err(|err| UpdateAppError::ConductorApiError(err))?;
 
-    let mut app = apps
+    let app = apps
         .into_iter()
         .find(|app| app.installed_app_id.eq(&app_id))
         .ok_or(UpdateAppError::AppNotFound(app_id.clone()))?;
 
     let new_dna_files = resolve_dna_files(bundle).await?;
+    let previous_dna_files = match previous_bundle {
+        Some(previous_bundle) => resolve_dna_files(previous_bundle).await?,
+        None => BTreeMap::new(),
+    };
+
+    let updates = plan_cell_updates(admin_ws, &app, &new_dna_files, &previous_dna_files).await?;
+
+    let mut results: Vec<CellUpdateResult> = updates
+        .iter()
+        .map(|update| CellUpdateResult {
+            role_name: update.role_name.clone(),
+            clone_id: update.clone_id.clone(),
+            dna_hash: update.dna_hash.clone(),
+            outcome: CellUpdateOutcome::Unchanged,
+        })
+        .collect();
+    let mut failure: Option<String> = None;
+
+    for (update, result) in updates.iter().zip(results.iter_mut()) {
+        let Some(new_coordinators) = &update.new_coordinators else {
+            continue;
+        };
+        if failure.is_some() {
+            result.outcome = CellUpdateOutcome::Skipped;
+            continue;
+        }
+
+        match update_coordinators(admin_ws, &update.dna_hash, new_coordinators.clone()).await {
+            Ok(()) => result.outcome = CellUpdateOutcome::Updated,
+            Err(err) => {
+                let error = format!("{err:?}");
+                log::error!(
+                    "Failed to update the coordinator zomes for role {}: {error}",
+                    update.role_name
+                );
+                result.outcome = CellUpdateOutcome::Failed(error.clone());
+                failure = Some(error);
+            }
+        }
+    }
+
+    let updated = results
+        .iter()
+        .any(|result| result.outcome == CellUpdateOutcome::Updated);
+    let app_was_running = matches!(app.status, AppInfoStatus::Running);
+
+    if failure.is_none() && updated && app_was_running {
+        if let Err(err) = restart_app(admin_ws, &app_id).await {
+            failure = Some(format!("{err:?}"));
+        }
+    }
+
+    let Some(error) = failure else {
+        if updated {
+            log::info!("Updated app {app_id:?}");
+        }
+        return Ok(results);
+    };
+
+    log::warn!("Rolling back the update of app {app_id}");
+    for (update, result) in updates.into_iter().zip(results.iter_mut()) {
+        if result.outcome != CellUpdateOutcome::Updated {
+            continue;
+        }
+
+        let rolled_back_outcome = if update.adds_zomes {
+            CellUpdateOutcome::NotRolledBack
+        } else {
+            CellUpdateOutcome::RolledBack
+        };
+        result.outcome = match update.rollback {
+            Rollback::Nothing => rolled_back_outcome,
+            Rollback::Restore(previous_coordinators) => {
+                match update_coordinators(admin_ws, &update.dna_hash, previous_coordinators).await {
+                    Ok(()) => rolled_back_outcome,
+                    Err(err) => CellUpdateOutcome::RollbackFailed(format!("{err:?}")),
+                }
+            }
+            Rollback::Unavailable => CellUpdateOutcome::RollbackFailed(String::from(
+                "the previous version of the coordinator zomes is not available",
+            )),
+        };
+    }
+
+    if app_was_running {
+        if let Err(err) = admin_ws.enable_app(app_id.clone()).await {
+            log::error!("Failed to enable app {app_id} after rolling back its update: {err:?}");
+        }
+    }
+
+    Err(UpdateAppError::UpdateRolledBack {
+        error,
+        cells: results,
+    })
+}
+
+/// Diffs the integrity and coordinator zomes of all the cells of the given app with the ones in the new bundle,
+/// without changing anything in the conductor
+pub async fn plan_update(
+    admin_ws: &AdminWebsocket,
+    app_id: &InstalledAppId,
+    bundle: AppBundle,
+) -> Result<Vec<CellUpdatePlan>, UpdateAppError> {
+    let apps = admin_ws
+        .list_apps(None)
+        .await
+        .map_err(|err| UpdateAppError::ConductorApiError(err))?;
+    let app = apps
+        .into_iter()
+        .find(|app| app.installed_app_id.eq(app_id))
+        .ok_or(UpdateAppError::AppNotFound(app_id.clone()))?;
+
+    let new_dna_files = resolve_dna_files(bundle).await?;
+
+    let mut plans: Vec<CellUpdatePlan> = Vec::new();
+
+    for (role_name, clone_id, dna_hash) in app_cells(&app, &new_dna_files)? {
+        let new_dna_file = &new_dna_files[&role_name];
+        let old_dna_def = admin_ws
+            .get_dna_definition(dna_hash.clone())
+            .await
+            .map_err(|err| UpdateAppError::ConductorApiError(err))?;
+
+        let mut coordinator_zomes: Vec<CoordinatorZomePlan> = Vec::new();
+        for (zome_name, coordinator_zome) in new_dna_file.dna_def().coordinator_zomes.iter() {
+            coordinator_zomes.push(CoordinatorZomePlan {
+                zome_name: zome_name.clone(),
+                change: coordinator_zome_change(&old_dna_def, zome_name, coordinator_zome)?,
+            });
+        }
+
+        plans.push(CellUpdatePlan {
+            role_name,
+            clone_id,
+            integrity_changed: integrity_changed(&old_dna_def, new_dna_file.dna_def())?,
+            dna_hash,
+            new_dna_hash: new_dna_file.dna_hash().clone(),
+            coordinator_zomes,
+        });
+    }
 
-    let mut updated = false;
+    Ok(plans)
+}
 
-    for (role_name, new_dna_file) in new_dna_files {
+/// The cells of the app for the roles in the new DNAs, as `(role_name, clone_id, dna_hash)`,
+/// skipping the cells that share their DNA with a previous one
+fn app_cells(
+    app: &AppInfo,
+    new_dna_files: &BTreeMap<RoleName, DnaFile>,
+) -> Result<Vec<(RoleName, Option<CloneId>, DnaHash)>, UpdateAppError> {
+    let mut app_cells: Vec<(RoleName, Option<CloneId>, DnaHash)> = Vec::new();
+    let mut dna_hashes: HashSet<DnaHash> = HashSet::new();
+
+    for role_name in new_dna_files.keys() {
         let cells = app
             .cell_info
-            .remove(&role_name)
+            .get(role_name)
             .ok_or(UpdateAppError::RoleNotFound(
                 role_name.clone(),
                 app.installed_app_id.clone(),
             ))?;
 
         for cell in cells {
-            let mut zomes: Vec<ZomeManifest> = Vec::new();
-            let mut resources: Vec<(PathBuf, ResourceBytes)> = Vec::new();
-
-            let dna_hash = match cell {
-                CellInfo::Provisioned(c) => c.cell_id.dna_hash().clone(),
-                CellInfo::Cloned(c) => c.cell_id.dna_hash().clone(),
-                CellInfo::Stem(c) => c.original_dna_hash.clone(),
+            // Clone cells, enabled or not, have their own DNA hash and need their coordinators updated too
+            let (dna_hash, clone_id) = match cell {
+                CellInfo::Provisioned(c) => (c.cell_id.dna_hash().clone(), None),
+                CellInfo::Cloned(c) => (c.cell_id.dna_hash().clone(), Some(c.clone_id.clone())),
+                CellInfo::Stem(c) => (c.original_dna_hash.clone(), None),
             };
-            let old_dna_def = admin_ws
-                .get_dna_definition(dna_hash.clone())
-                .await
-                .map_err(|err| UpdateAppError::ConductorApiError(err))?;
-
-            for (zome_name, coordinator_zome) in new_dna_file.dna_def().coordinator_zomes.iter() {
-                let deps = coordinator_zome
-                    .clone()
-                    .erase_type()
-                    .dependencies()
-                    .to_vec();
-                let dependencies = deps
-                    .into_iter()
-                    .map(|name| ZomeDependency { name })
-                    .collect();
-
-                if let Some(old_zome_def) = old_dna_def
-                    .coordinator_zomes
-                    .iter()
-                    .find(|(zome, _)| zome.eq(&zome_name))
-                {
-                    if !old_zome_def
-                        .1
-                        .wasm_hash(&zome_name)?
-                        .eq(&coordinator_zome.wasm_hash(&zome_name)?)
-                    {
-                        log::info!("Updating coordinator zome {zome_name} for role {role_name}");
-                        let resource_path = PathBuf::from(zome_name.0.to_string());
-                        zomes.push(ZomeManifest {
-                            name: zome_name.clone(),
-                            hash: None,
-                            dylib: None,
-                            location: ZomeLocation::Bundled(resource_path.clone()),
-                            dependencies: Some(dependencies),
-                        });
-                        let wasm = new_dna_file.get_wasm_for_zome(&zome_name)?;
-                        resources.push((resource_path, wasm.code().to_vec().into()));
-                    }
-                } else {
-                    log::info!("Adding new coordinator zome {zome_name} for role {role_name}");
-                    let resource_path = PathBuf::from(zome_name.0.to_string());
-                    zomes.push(ZomeManifest {
-                        name: zome_name.clone(),
-                        hash: None,
-                        dylib: None,
-                        location: ZomeLocation::Bundled(resource_path.clone()),
-                        dependencies: Some(dependencies),
-                    });
-                    let wasm = new_dna_file.get_wasm_for_zome(&zome_name)?;
-                    resources.push((resource_path, wasm.code().to_vec().into()));
-                }
+            if dna_hashes.insert(dna_hash.clone()) {
+                app_cells.push((role_name.clone(), clone_id, dna_hash));
             }
+        }
+    }
+
+    Ok(app_cells)
+}
+
+/// Diffs the coordinator zomes of all the cells of the app with the ones in the new DNAs
+async fn plan_cell_updates(
+    admin_ws: &AdminWebsocket,
+    app: &AppInfo,
+    new_dna_files: &BTreeMap<RoleName, DnaFile>,
+    previous_dna_files: &BTreeMap<RoleName, DnaFile>,
+) -> Result<Vec<CellUpdate>, UpdateAppError> {
+    let mut updates: Vec<CellUpdate> = Vec::new();
+
+    for (role_name, clone_id, dna_hash) in app_cells(app, new_dna_files)? {
+        let new_dna_file = &new_dna_files[&role_name];
+        let cell_name = match &clone_id {
+            Some(clone_id) => format!("clone {clone_id} of role {role_name}"),
+            None => format!("role {role_name}"),
+        };
+
+        let old_dna_def = admin_ws
+            .get_dna_definition(dna_hash.clone())
+            .await
+            .map_err(|err| UpdateAppError::ConductorApiError(err))?;
+
+        if integrity_changed(&old_dna_def, new_dna_file.dna_def())? {
+            return Err(UpdateAppError::IntegrityChanged {
+                role: role_name.clone(),
+                old_dna: dna_hash,
+                new_dna: new_dna_file.dna_hash().clone(),
+            });
+        }
 
-            if !zomes.is_empty() {
-                let source: CoordinatorBundle =
-                    Bundle::new(CoordinatorManifest { zomes }, resources, PathBuf::from("/"))?
-                        .into();
-                let req = UpdateCoordinatorsPayload {
-                    dna_hash,
-                    source: holochain_types::prelude::CoordinatorSource::Bundle(Box::new(source)),
-                };
-
-                admin_ws
-                    .update_coordinators(req)
-                    .await
-                    .map_err(|err| UpdateAppError::ConductorApiError(err))?;
-                updated = true;
+        let mut new_zomes: Vec<(ZomeName, Vec<ZomeName>, Vec<u8>)> = Vec::new();
+        let mut adds_zomes = false;
+        // `None` if the previous version of any of the replaced zomes is not available
+        let mut previous_zomes: Option<Vec<(ZomeName, Vec<ZomeName>, Vec<u8>)>> = Some(Vec::new());
+
+        for (zome_name, coordinator_zome) in new_dna_file.dna_def().coordinator_zomes.iter() {
+            match coordinator_zome_change(&old_dna_def, zome_name, coordinator_zome)? {
+                CoordinatorZomeChange::Unchanged => continue,
+                CoordinatorZomeChange::Added => {
+                    log::info!("Adding new coordinator zome {zome_name} for {cell_name}");
+                    adds_zomes = true;
+                }
+                CoordinatorZomeChange::Replaced => {
+                    log::info!("Updating coordinator zome {zome_name} for {cell_name}");
+
+                    let old_zome_def = old_dna_def
+                        .coordinator_zomes
+                        .iter()
+                        .find(|(zome, _)| zome.eq(zome_name))
+                        .map(|(_, zome_def)| zome_def.clone().erase_type());
+                    let previous_zome = match old_zome_def {
+                        Some(old_zome_def) => previous_wasm(
+                            previous_dna_files.get(&role_name),
+                            zome_name,
+                            &old_zome_def.wasm_hash(zome_name)?,
+                        )
+                        .map(|wasm| (old_zome_def.dependencies().to_vec(), wasm)),
+                        None => None,
+                    };
+                    previous_zomes = match (previous_zomes, previous_zome) {
+                        (Some(mut previous_zomes), Some((dependencies, wasm))) => {
+                            previous_zomes.push((zome_name.clone(), dependencies, wasm));
+                            Some(previous_zomes)
+                        }
+                        _ => None,
+                    };
+                }
             }
+
+            let dependencies = coordinator_zome
+                .clone()
+                .erase_type()
+                .dependencies()
+                .to_vec();
+            let wasm = new_dna_file.get_wasm_for_zome(zome_name)?;
+            new_zomes.push((zome_name.clone(), dependencies, wasm.code().to_vec()));
         }
+
+        let (new_coordinators, rollback) = if new_zomes.is_empty() {
+            (None, Rollback::Nothing)
+        } else {
+            let rollback = match previous_zomes {
+                Some(previous_zomes) if previous_zomes.is_empty() => Rollback::Nothing,
+                Some(previous_zomes) => Rollback::Restore(coordinator_bundle(previous_zomes)?),
+                None => {
+                    log::warn!("The previous coordinator zomes for {cell_name} are not available, it won't be possible to roll it back");
+                    Rollback::Unavailable
+                }
+            };
+            (Some(coordinator_bundle(new_zomes)?), rollback)
+        };
+
+        updates.push(CellUpdate {
+            role_name,
+            clone_id,
+            dna_hash,
+            new_coordinators,
+            adds_zomes,
+            rollback,
+        });
+    }
+
+    Ok(updates)
+}
+
+/// How the given coordinator zome of the new DNA compares with the one in the installed DNA, by their wasm hash
+fn coordinator_zome_change(
+    old_dna_def: &DnaDef,
+    zome_name: &ZomeName,
+    new_zome_def: &CoordinatorZomeDef,
+) -> Result<CoordinatorZomeChange, UpdateAppError> {
+    let Some((_, old_zome_def)) = old_dna_def
+        .coordinator_zomes
+        .iter()
+        .find(|(zome, _)| zome.eq(zome_name))
+    else {
+        return Ok(CoordinatorZomeChange::Added);
+    };
+
+    if old_zome_def
+        .wasm_hash(zome_name)?
+        .eq(&new_zome_def.wasm_hash(zome_name)?)
+    {
+        Ok(CoordinatorZomeChange::Unchanged)
+    } else {
+        Ok(CoordinatorZomeChange::Replaced)
+    }
+}
+
+/// Whether the integrity zomes of the new DNA differ from the installed ones,
+/// in which case the coordinator zomes can't be updated: the new DNA is a different DNA altogether
+fn integrity_changed(old_dna_def: &DnaDef, new_dna_def: &DnaDef) -> Result<bool, UpdateAppError> {
+    if old_dna_def.integrity_zomes.len() != new_dna_def.integrity_zomes.len() {
+        return Ok(true);
     }
 
-    if updated {
-        if let AppInfoStatus::Running = app.status {
-            admin_ws
-                .disable_app(app_id.clone())
-                .await
-                .map_err(|err| UpdateAppError::ConductorApiError(err))?;
-            admin_ws
-                .enable_app(app_id.clone())
-                .await
-                .map_err(|err| UpdateAppError::ConductorApiError(err))?;
+    for ((old_name, old_zome), (new_name, new_zome)) in old_dna_def
+        .integrity_zomes
+        .iter()
+        .zip(new_dna_def.integrity_zomes.iter())
+    {
+        if !old_name.eq(new_name)
+            || !old_zome
+                .wasm_hash(old_name)?
+                .eq(&new_zome.wasm_hash(new_name)?)
+        {
+            return Ok(true);
         }
-        log::info!("Updated app {app_id:?}");
     }
 
+    Ok(false)
+}
+
+/// Returns the wasm for the given zome in the previous version of the DNA,
+/// if it's the version that is currently installed in the conductor
+fn previous_wasm(
+    previous_dna_file: Option<&DnaFile>,
+    zome_name: &ZomeName,
+    installed_wasm_hash: &WasmHash,
+) -> Option<Vec<u8>> {
+    let previous_dna_file = previous_dna_file?;
+    let (_, previous_zome_def) = previous_dna_file
+        .dna_def()
+        .coordinator_zomes
+        .iter()
+        .find(|(zome, _)| zome.eq(zome_name))?;
+
+    if !previous_zome_def
+        .wasm_hash(zome_name)
+        .ok()?
+        .eq(installed_wasm_hash)
+    {
+        return None;
+    }
+
+    let wasm = previous_dna_file.get_wasm_for_zome(zome_name).ok()?;
+    Some(wasm.code().to_vec())
+}
+
+/// Builds a bundle with the given coordinator zomes, as `(name, dependencies, wasm)`
+fn coordinator_bundle(
+    zomes: Vec<(ZomeName, Vec<ZomeName>, Vec<u8>)>,
+) -> Result<CoordinatorBundle, UpdateAppError> {
+    let mut manifests: Vec<ZomeManifest> = Vec::new();
+    let mut resources: Vec<(PathBuf, ResourceBytes)> = Vec::new();
+
+    for (zome_name, dependencies, wasm) in zomes {
+        let resource_path = PathBuf::from(zome_name.0.to_string());
+        manifests.push(ZomeManifest {
+            name: zome_name,
+            hash: None,
+            dylib: None,
+            location: ZomeLocation::Bundled(resource_path.clone()),
+            dependencies: Some(
+                dependencies
+                    .into_iter()
+                    .map(|name| ZomeDependency { name })
+                    .collect(),
+            ),
+        });
+        resources.push((resource_path, wasm.into()));
+    }
+
+    let bundle = Bundle::new(
+        CoordinatorManifest { zomes: manifests },
+        resources,
+        PathBuf::from("/"),
+    )?;
+
+    Ok(bundle.into())
+}
+
+async fn update_coordinators(
+    admin_ws: &AdminWebsocket,
+    dna_hash: &DnaHash,
+    coordinators: CoordinatorBundle,
+) -> Result<(), ConductorApiError> {
+    admin_ws
+        .update_coordinators(UpdateCoordinatorsPayload {
+            dna_hash: dna_hash.clone(),
+            source: holochain_types::prelude::CoordinatorSource::Bundle(Box::new(coordinators)),
+        })
+        .await
+}
+
+/// Disables and enables the app so that the cells pick up their new coordinator zomes
+async fn restart_app(
+    admin_ws: &AdminWebsocket,
+    app_id: &InstalledAppId,
+) -> Result<(), ConductorApiError> {
+    admin_ws.disable_app(app_id.clone()).await?;
+    admin_ws.enable_app(app_id.clone()).await?;
     Ok(())
 }
 
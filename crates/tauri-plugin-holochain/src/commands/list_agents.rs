@@ -0,0 +1,7 @@
+use crate::{HolochainExt, LocalAgent};
+use tauri::{command, AppHandle, Runtime};
+
+#[command]
+pub(crate) async fn list_agents<R: Runtime>(app: AppHandle<R>) -> crate::Result<Vec<LocalAgent>> {
+    app.holochain()?.list_agents().await
+}
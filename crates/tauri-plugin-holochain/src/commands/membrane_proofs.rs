@@ -0,0 +1,35 @@
+use tauri::{command, AppHandle, Runtime};
+
+use crate::{membrane_proofs::membrane_proof_from_bytes, HolochainExt};
+
+/// Answers a `membrane-proof-requested` event emitted by `UiMembraneProofProvider`
+///
+/// * `request_id` - the id of the request received in the event
+/// * `membrane_proof` - the bytes of the membrane proof, or `null` if the role does not need one
+#[command]
+pub(crate) async fn provide_membrane_proof<R: Runtime>(
+    app: AppHandle<R>,
+    request_id: String,
+    membrane_proof: Option<Vec<u8>>,
+) -> crate::Result<()> {
+    let sender = app
+        .holochain()?
+        .holochain_runtime
+        .pending_membrane_proof_requests
+        .lock()
+        .await
+        .remove(&request_id)
+        .ok_or(crate::Error::MembraneProofError(format!(
+            "there is no pending request with id {request_id}"
+        )))?;
+
+    sender
+        .send(membrane_proof.map(membrane_proof_from_bytes))
+        .map_err(|_| {
+            crate::Error::MembraneProofError(format!(
+                "the installation waiting for request {request_id} was cancelled"
+            ))
+        })?;
+
+    Ok(())
+}
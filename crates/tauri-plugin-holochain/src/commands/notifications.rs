@@ -0,0 +1,11 @@
+use crate::HolochainExt;
+use tauri::{command, AppHandle, Runtime};
+
+#[command]
+pub(crate) fn set_app_notifications_muted<R: Runtime>(
+    app: AppHandle<R>,
+    app_id: String,
+    muted: bool,
+) -> crate::Result<()> {
+    app.holochain()?.set_app_notifications_muted(app_id, muted)
+}
@@ -2,27 +2,38 @@ use holochain::{
     conductor::api::ZomeCall,
     prelude::{CapSecret, CellId, ExternIO, FunctionName, Timestamp, ZomeCallUnsigned, ZomeName},
 };
-use holochain_client::AgentPubKey;
+use holochain_client::{AgentPubKey, InstalledAppId};
+use holochain_conductor_api::CellInfo;
 use holochain_types::prelude::Signature;
 use lair_keystore_api::LairClient;
 use serde::Deserialize;
-use tauri::{command, AppHandle, Runtime};
+use tauri::{command, AppHandle, Runtime, Window};
 
-use crate::HolochainExt;
+use crate::{HolochainExt, HolochainPlugin, ZomeCallSignerConfig};
 
 #[command]
 pub(crate) async fn sign_zome_call<R: Runtime>(
     app_handle: AppHandle<R>,
+    window: Window<R>,
     zome_call_unsigned: ZomeCallUnsignedTauri,
 ) -> crate::Result<ZomeCall> {
-    let zome_call_unsigned_converted: ZomeCallUnsigned = zome_call_unsigned.into();
+    let holochain = app_handle.holochain()?;
+    let zome_call_unsigned_converted =
+        zome_call_unsigned.into_zome_call_unsigned(&holochain.config.zome_call_signer)?;
+
+    authorize_zome_call(
+        holochain,
+        window.label(),
+        &zome_call_unsigned_converted.provenance,
+        &zome_call_unsigned_converted.cell_id,
+    )
+    .await?;
 
     let signed_zome_call = sign_zome_call_with_client(
         zome_call_unsigned_converted,
-        &app_handle
-            .holochain()?
+        &holochain
             .holochain_runtime
-            .conductor_handle
+            .conductor_handle()?
             .keystore()
             .lair_client()
             .clone(),
@@ -32,6 +43,91 @@ pub(crate) async fn sign_zome_call<R: Runtime>(
     Ok(signed_zome_call)
 }
 
+/// Checks that the zome call is made by the agent of the app the given window belongs to,
+/// and targets one of the cells of that app
+///
+/// Returns the id of the app the window belongs to
+pub(crate) async fn authorize_zome_call<R: Runtime>(
+    holochain: &HolochainPlugin<R>,
+    window_label: &str,
+    provenance: &AgentPubKey,
+    cell_id: &CellId,
+) -> crate::Result<InstalledAppId> {
+    let scope = match holochain.get_app_id_for_window(window_label).await {
+        Some(app_id) => Some(app_zome_call_scope(holochain, app_id).await?),
+        None => None,
+    };
+
+    check_zome_call_scope(window_label, scope.as_ref(), provenance, cell_id)
+}
+
+/// The agent and the cells a window can sign zome calls for, those of the app it belongs to
+struct AppZomeCallScope {
+    app_id: InstalledAppId,
+    agent_pub_key: AgentPubKey,
+    cell_ids: Vec<CellId>,
+}
+
+async fn app_zome_call_scope<R: Runtime>(
+    holochain: &HolochainPlugin<R>,
+    app_id: InstalledAppId,
+) -> crate::Result<AppZomeCallScope> {
+    let Some(app_info) = holochain
+        .holochain_runtime
+        .conductor_handle()?
+        .get_app_info(&app_id)
+        .await?
+    else {
+        return Err(crate::Error::AppDoesNotExist(app_id));
+    };
+
+    let cell_ids = app_info
+        .cell_info
+        .values()
+        .flatten()
+        .filter_map(|cell_info| match cell_info {
+            CellInfo::Provisioned(c) => Some(c.cell_id.clone()),
+            CellInfo::Cloned(c) => Some(c.cell_id.clone()),
+            CellInfo::Stem(_) => None,
+        })
+        .collect();
+
+    Ok(AppZomeCallScope {
+        app_id,
+        agent_pub_key: app_info.agent_pub_key,
+        cell_ids,
+    })
+}
+
+/// Checks the zome call against the scope of the app the window belongs to, `None` if it doesn't belong to any
+fn check_zome_call_scope(
+    window_label: &str,
+    scope: Option<&AppZomeCallScope>,
+    provenance: &AgentPubKey,
+    cell_id: &CellId,
+) -> crate::Result<InstalledAppId> {
+    let Some(scope) = scope else {
+        return Err(crate::Error::UnauthorizedZomeCall(format!(
+            "window {window_label} does not belong to any app"
+        )));
+    };
+    let app_id = &scope.app_id;
+
+    if !provenance.eq(&scope.agent_pub_key) {
+        return Err(crate::Error::UnauthorizedZomeCall(format!(
+            "provenance {provenance} is not the agent for app {app_id}"
+        )));
+    }
+
+    if !scope.cell_ids.contains(cell_id) {
+        return Err(crate::Error::UnauthorizedZomeCall(format!(
+            "cell {cell_id} does not belong to app {app_id}"
+        )));
+    }
+
+    Ok(app_id.clone())
+}
+
 /// Signs an unsigned zome call with the given LairClient
 pub async fn sign_zome_call_with_client(
     zome_call_unsigned: ZomeCallUnsigned,
@@ -70,6 +166,7 @@ pub async fn sign_zome_call_with_client(
 /// behavior of tauri's IPC channel (serde serialization)
 /// nonce is a byte array [u8, 32] because holochain's nonce type seems to
 /// have "non-serde" deserialization behavior.
+/// expires_at is optional: if omitted, it's set from the conductor's clock with the configured expiry
 #[derive(Deserialize, Debug)]
 pub struct ZomeCallUnsignedTauri {
     pub provenance: AgentPubKey,
@@ -79,12 +176,30 @@ pub struct ZomeCallUnsignedTauri {
     pub cap_secret: Option<CapSecret>,
     pub payload: ExternIO,
     pub nonce: [u8; 32],
-    pub expires_at: Timestamp,
+    #[serde(default)]
+    pub expires_at: Option<Timestamp>,
 }
 
-impl Into<ZomeCallUnsigned> for ZomeCallUnsignedTauri {
-    fn into(self) -> ZomeCallUnsigned {
-        ZomeCallUnsigned {
+impl ZomeCallUnsignedTauri {
+    /// Converts this zome call into the one to sign, applying the given signer configuration
+    pub fn into_zome_call_unsigned(
+        self,
+        config: &ZomeCallSignerConfig,
+    ) -> crate::Result<ZomeCallUnsigned> {
+        if self.cap_secret.is_some() && !config.allow_cap_secret {
+            return Err(crate::Error::UnauthorizedZomeCall(String::from(
+                "zome calls with a cap_secret are not allowed",
+            )));
+        }
+
+        let expires_at = match self.expires_at {
+            Some(expires_at) => expires_at,
+            None => Timestamp::from_micros(
+                Timestamp::now().as_micros() + config.zome_call_expiry.as_micros() as i64,
+            ),
+        };
+
+        Ok(ZomeCallUnsigned {
             provenance: self.provenance,
             cell_id: self.cell_id,
             zome_name: self.zome_name,
@@ -92,8 +207,8 @@ impl Into<ZomeCallUnsigned> for ZomeCallUnsignedTauri {
             cap_secret: self.cap_secret,
             payload: self.payload,
             nonce: self.nonce.into(),
-            expires_at: self.expires_at,
-        }
+            expires_at,
+        })
     }
 }
 
@@ -111,3 +226,72 @@ impl Clone for ZomeCallUnsignedTauri {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use holochain::prelude::DnaHash;
+
+    use super::*;
+
+    fn agent(byte: u8) -> AgentPubKey {
+        AgentPubKey::from_raw_36(vec![byte; 36])
+    }
+
+    fn cell(dna_byte: u8, agent_byte: u8) -> CellId {
+        CellId::new(DnaHash::from_raw_36(vec![dna_byte; 36]), agent(agent_byte))
+    }
+
+    fn forum_scope() -> AppZomeCallScope {
+        AppZomeCallScope {
+            app_id: String::from("forum"),
+            agent_pub_key: agent(1),
+            // Provisioned and cloned cells
+            cell_ids: vec![cell(1, 1), cell(2, 1)],
+        }
+    }
+
+    #[test]
+    fn windows_can_only_sign_for_the_agent_and_cells_of_their_app() {
+        let scope = forum_scope();
+        let cases: Vec<(&str, Option<&AppZomeCallScope>, AgentPubKey, CellId, bool)> = vec![
+            ("own cell", Some(&scope), agent(1), cell(1, 1), true),
+            ("own clone cell", Some(&scope), agent(1), cell(2, 1), true),
+            (
+                "other app's cell",
+                Some(&scope),
+                agent(1),
+                cell(3, 1),
+                false,
+            ),
+            (
+                "other app's cell for another agent",
+                Some(&scope),
+                agent(1),
+                cell(1, 2),
+                false,
+            ),
+            ("other agent", Some(&scope), agent(2), cell(1, 1), false),
+            (
+                "other agent in its own cell",
+                Some(&scope),
+                agent(2),
+                cell(1, 2),
+                false,
+            ),
+            ("window without an app", None, agent(1), cell(1, 1), false),
+        ];
+
+        for (case, scope, provenance, cell_id, authorized) in cases {
+            let result = check_zome_call_scope("window", scope, &provenance, &cell_id);
+
+            if authorized {
+                assert_eq!(result.ok(), Some(String::from("forum")), "{case}");
+            } else {
+                assert!(
+                    matches!(result, Err(crate::Error::UnauthorizedZomeCall(_))),
+                    "{case}"
+                );
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{ops::RangeInclusive, sync::Arc};
 
 use holochain::{
     conductor::{
@@ -15,9 +15,39 @@ use url2::Url2;
 
 use crate::filesystem::FileSystem;
 
+/// Which port the admin interface of the conductor is served on
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum AdminPort {
+    /// Always this port, e.g. to reach the conductor with `hc sandbox call` during development
+    Fixed(u16),
+    /// The first port in this range that is free when the conductor starts
+    Range(RangeInclusive<u16>),
+    /// A port assigned by the OS when the conductor binds the interface
+    #[default]
+    OsAssigned,
+}
+
+impl AdminPort {
+    /// The ports to try to bind the admin interface to, in order, `0` to let the OS assign one
+    ///
+    /// The ports in a range are not checked beforehand, since another process could take them before the conductor
+    /// binds them: the conductor tries to add its admin interface on each of them until one can be bound instead
+    pub(crate) fn ports_to_bind(&self) -> crate::Result<Vec<u16>> {
+        match self {
+            AdminPort::Fixed(port) => Ok(vec![*port]),
+            AdminPort::Range(range) if range.is_empty() => Err(crate::Error::AdminPortUnavailable(
+                format!("the range {}..={} is empty", range.start(), range.end()),
+            )),
+            AdminPort::Range(range) => Ok(range.clone().collect()),
+            AdminPort::OsAssigned => Ok(vec![0]),
+        }
+    }
+}
+
+/// The config for the conductor, without any admin interface: it's added with `admin_interface_config()`
+/// once the conductor is built, so that the conductor is not built again for each port that can't be bound
 pub fn conductor_config(
     fs: &FileSystem,
-    admin_port: u16,
     lair_root: KeystorePath,
     bootstrap_url: Url2,
     signal_url: Url2,
@@ -48,6 +78,11 @@ pub fn conductor_config(
 
     config.network = network_config;
 
+    config
+}
+
+/// The config for the admin interface of the conductor, served on the given port or on one assigned by the OS if it's `0`
+pub fn admin_interface_config(admin_port: u16) -> AdminInterfaceConfig {
     // TODO: uncomment when we can set a custom origin for holochain-client-rust
     // let mut origins: HashSet<String> = HashSet::new();
     // origins.insert(String::from("localhost")); // Compatible with the url of the main window: tauri://localhost
@@ -55,12 +90,48 @@ pub fn conductor_config(
 
     let allowed_origins = AllowedOrigins::Any;
 
-    config.admin_interfaces = Some(vec![AdminInterfaceConfig {
+    AdminInterfaceConfig {
         driver: InterfaceDriver::Websocket {
             port: admin_port,
             allowed_origins,
         },
-    }]);
+    }
+}
 
-    config
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_port_is_bound_as_is() {
+        assert_eq!(AdminPort::Fixed(8888).ports_to_bind().unwrap(), vec![8888]);
+    }
+
+    #[test]
+    fn os_assigned_port_binds_port_zero() {
+        assert_eq!(AdminPort::OsAssigned.ports_to_bind().unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn range_tries_every_port_in_order() {
+        assert_eq!(
+            AdminPort::Range(9000..=9002).ports_to_bind().unwrap(),
+            vec![9000, 9001, 9002]
+        );
+        assert_eq!(
+            AdminPort::Range(9000..=9000).ports_to_bind().unwrap(),
+            vec![9000]
+        );
+    }
+
+    #[test]
+    fn empty_range_is_rejected() {
+        #[allow(clippy::reversed_empty_ranges)]
+        let range = 9002..=9000;
+
+        assert!(matches!(
+            AdminPort::Range(range).ports_to_bind(),
+            Err(crate::Error::AdminPortUnavailable(_))
+        ));
+    }
 }
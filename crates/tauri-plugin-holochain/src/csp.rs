@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use holochain_client::InstalledAppId;
+
+/// Placeholder for the `happ://` origin of the app in custom policies
+pub const ORIGIN_PLACEHOLDER: &'static str = "{origin}";
+/// Placeholder for the port of the app websocket of the app in custom policies
+pub const APP_WEBSOCKET_PORT_PLACEHOLDER: &'static str = "{app_websocket_port}";
+
+/// Content-Security-Policy served with the responses for the `happ://` protocol
+///
+/// By default, hApp UIs may only load resources from their own origin and only connect to their app websocket,
+/// so that untrusted UIs can't exfiltrate data to arbitrary servers
+///
+/// Custom policies can use the `{origin}` and `{app_websocket_port}` placeholders, which get replaced with
+/// the values for the app being served. If no app interface was assigned to the app yet,
+/// the sources with the `{app_websocket_port}` placeholder are left out
+#[derive(Clone, Debug, Default)]
+pub struct ContentSecurityPolicyConfig {
+    /// Policy to use for all apps instead of the default one
+    pub default_policy: Option<String>,
+    /// Policies for specific apps, which take precedence over `default_policy`
+    pub app_policies: HashMap<InstalledAppId, String>,
+}
+
+impl ContentSecurityPolicyConfig {
+    /// Builds the Content-Security-Policy header value for the given app
+    ///
+    /// * `app_id` - the app whose UI is being served
+    /// * `origin` - the `happ://` origin for the app
+    /// * `app_websocket_port` - the port of the app websocket the UI is allowed to connect to, if one was assigned to the app
+    pub fn policy_for_app(
+        &self,
+        app_id: &InstalledAppId,
+        origin: &str,
+        app_websocket_port: Option<u16>,
+    ) -> String {
+        let policy = match self.app_policies.get(app_id) {
+            Some(policy) => policy.clone(),
+            None => self
+                .default_policy
+                .clone()
+                .unwrap_or_else(|| default_policy()),
+        };
+
+        let policy = match app_websocket_port {
+            Some(app_websocket_port) => policy.replace(
+                APP_WEBSOCKET_PORT_PLACEHOLDER,
+                &app_websocket_port.to_string(),
+            ),
+            None => without_app_websocket_sources(&policy),
+        };
+        policy.replace(ORIGIN_PLACEHOLDER, origin)
+    }
+}
+
+/// Removes the sources that use the `{app_websocket_port}` placeholder from all the directives of the given policy
+fn without_app_websocket_sources(policy: &str) -> String {
+    policy
+        .split(';')
+        .map(|directive| {
+            directive
+                .split_whitespace()
+                .filter(|source| !source.contains(APP_WEBSOCKET_PORT_PLACEHOLDER))
+                .collect::<Vec<&str>>()
+                .join(" ")
+        })
+        .filter(|directive| !directive.is_empty())
+        .collect::<Vec<String>>()
+        .join("; ")
+}
+
+/// The policy used when no custom policy is configured
+///
+/// Only the app's own origin, its app websocket and tauri's IPC are reachable
+pub fn default_policy() -> String {
+    [
+        "default-src 'self' {origin}",
+        "script-src 'self' {origin} 'wasm-unsafe-eval'",
+        "style-src 'self' {origin} 'unsafe-inline'",
+        "img-src 'self' {origin} data: blob:",
+        "font-src 'self' {origin} data:",
+        "media-src 'self' {origin} blob:",
+        "connect-src 'self' {origin} ipc: http://ipc.localhost ws://localhost:{app_websocket_port} ws://127.0.0.1:{app_websocket_port}",
+        "frame-src 'self' {origin}",
+        "object-src 'none'",
+        "base-uri 'self'",
+        "form-action 'self'",
+    ]
+    .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGIN: &str = "happ://forum";
+
+    #[test]
+    fn default_policy_only_allows_the_app_origin_and_websocket() {
+        let policy = ContentSecurityPolicyConfig::default().policy_for_app(
+            &InstalledAppId::from("forum"),
+            ORIGIN,
+            Some(8888),
+        );
+
+        assert!(!policy.contains(ORIGIN_PLACEHOLDER));
+        assert!(!policy.contains(APP_WEBSOCKET_PORT_PLACEHOLDER));
+        assert!(policy.contains("default-src 'self' happ://forum;"));
+        assert!(policy.contains("ws://localhost:8888 ws://127.0.0.1:8888"));
+        assert!(policy.contains("object-src 'none'"));
+        assert!(!policy.contains("*"));
+    }
+
+    #[test]
+    fn custom_policies_get_their_placeholders_substituted() {
+        let config = ContentSecurityPolicyConfig {
+            default_policy: Some(String::from(
+                "default-src {origin}; connect-src {origin} ws://localhost:{app_websocket_port}",
+            )),
+            app_policies: HashMap::from([(
+                InstalledAppId::from("trusted"),
+                String::from("default-src *; frame-src {origin} {origin}"),
+            )]),
+        };
+
+        assert_eq!(
+            config.policy_for_app(&InstalledAppId::from("forum"), ORIGIN, Some(8888)),
+            "default-src happ://forum; connect-src happ://forum ws://localhost:8888"
+        );
+        assert_eq!(
+            config.policy_for_app(
+                &InstalledAppId::from("trusted"),
+                "happ://trusted",
+                Some(8888)
+            ),
+            "default-src *; frame-src happ://trusted happ://trusted"
+        );
+    }
+
+    #[test]
+    fn policies_without_an_app_websocket_port_leave_out_its_sources() {
+        let policy = ContentSecurityPolicyConfig::default().policy_for_app(
+            &InstalledAppId::from("forum"),
+            ORIGIN,
+            None,
+        );
+
+        assert!(!policy.contains(APP_WEBSOCKET_PORT_PLACEHOLDER));
+        assert!(!policy.contains("ws://"));
+        assert!(
+            policy.contains("connect-src 'self' happ://forum ipc: http://ipc.localhost; frame-src")
+        );
+        assert_eq!(
+            policy.split("; ").count(),
+            default_policy().split("; ").count()
+        );
+    }
+}
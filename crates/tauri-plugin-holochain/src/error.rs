@@ -1,10 +1,18 @@
-use holochain::{conductor::error::ConductorError, prelude::SerializedBytesError};
-use holochain_client::ConductorApiError;
+use std::time::Duration;
+
+use holochain::{
+    conductor::error::ConductorError,
+    prelude::{RoleName, SerializedBytesError},
+};
+use holochain_client::{AgentPubKey, ConductorApiError};
 use mr_bundle::error::MrBundleError;
 use one_err::OneErr;
 use serde::{ser::Serializer, Serialize};
 
-use crate::{commands::install_web_app::UpdateAppError, filesystem::FileSystemError};
+use crate::{
+    commands::install_web_app::UpdateAppError, filesystem::FileSystemError,
+    publisher::PublisherSignatureError,
+};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -56,6 +64,9 @@ pub enum Error {
     #[error("Sign zome call error: {0}")]
     SignZomeCallError(String),
 
+    #[error("Unauthorized zome call: {0}")]
+    UnauthorizedZomeCall(String),
+
     #[error("Admin websocket error: {0}")]
     AdminWebsocketError(String),
 
@@ -74,8 +85,80 @@ pub enum Error {
     #[error("App \"{0}\" does not have any UI")]
     AppDoesNotHaveUIError(String),
 
+    #[error("Asset path \"{0}\" is outside of the UI directory")]
+    ForbiddenAssetPath(String),
+
+    #[error("App \"{app_id}\" would be served from the same origin as app \"{other_app_id}\"")]
+    HostLabelTaken {
+        app_id: String,
+        other_app_id: String,
+    },
+
+    #[error("Window \"{0}\" does not belong to any app")]
+    WindowWithoutApp(String),
+
+    #[error("Error showing notification: {0}")]
+    NotificationError(String),
+
+    #[error("Agent {0} was not found in the keystore")]
+    AgentNotFound(AgentPubKey),
+
+    #[error("Invalid recovery phrase: {0}")]
+    InvalidRecoveryPhrase(String),
+
+    #[error("A master seed already exists in this device")]
+    MasterSeedAlreadyExists,
+
+    #[error("No master seed was created or imported in this device")]
+    MasterSeedNotFound,
+
+    #[error("Membrane proof error: {0}")]
+    MembraneProofError(String),
+
+    #[error("The membrane proof for role {role_name} was not provided within {timeout:?}")]
+    MembraneProofRequestTimedOut {
+        role_name: RoleName,
+        timeout: Duration,
+    },
+
+    #[error("Invalid install options: {0}")]
+    InvalidInstallOptions(String),
+
+    #[error("App \"{0}\" is not awaiting membrane proofs")]
+    AppNotAwaitingMembraneProofs(String),
+
     #[error(transparent)]
     UpdateAppError(#[from] UpdateAppError),
+
+    #[error("Admin port unavailable: {0}")]
+    AdminPortUnavailable(String),
+
+    /// Errors before the timeout elapses are returned as they are, so there is no underlying error to report
+    #[error("Holochain did not start within {timeout:?}: {stage} timed out")]
+    StartupTimeout {
+        timeout: Duration,
+        /// What was still pending when the timeout elapsed
+        stage: String,
+    },
+
+    #[error("The conductor is not running, it's being restarted or failed to restart")]
+    ConductorNotRunning,
+
+    #[error("The previous conductor was still in use {timeout:?} after shutting it down")]
+    ConductorNotReleased { timeout: Duration },
+
+    #[error("Could not connect to the admin interface at port {port}")]
+    AdminInterfaceConnectionError {
+        port: u16,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("Update channel error: {0}")]
+    UpdateChannelError(String),
+
+    #[error(transparent)]
+    PublisherSignatureError(#[from] PublisherSignatureError),
 }
 
 impl Serialize for Error {
@@ -6,8 +6,17 @@ use std::{fs, io::Write};
 use holochain::prelude::*;
 use holochain_types::web_app::WebAppBundle;
 use mr_bundle::error::MrBundleError;
+use serde::de::DeserializeOwned;
 use zip::result::ZipError;
 
+use crate::{
+    bundled_apps::BundledAppsStore,
+    install_options::{AppAwaitingMembraneProofs, AppsAwaitingMembraneProofsStore},
+    notifications::NotificationsSettingsStore,
+    origin::app_id_host_label,
+    update_channel::AppliedReleasesStore,
+};
+
 pub struct FileSystem {
     pub app_data_dir: PathBuf,
     pub bundle_store: BundleStore,
@@ -50,18 +59,36 @@ impl FileSystem {
 pub struct BundleStore {
     path: PathBuf,
     pub installed_apps_store: InstalledAppsStore,
+    pub notifications_settings_store: NotificationsSettingsStore,
+    pub(crate) apps_awaiting_membrane_proofs_store: AppsAwaitingMembraneProofsStore,
+    host_labels_store: HostLabelsStore,
+    pub(crate) applied_releases_store: AppliedReleasesStore,
+    pub(crate) bundled_apps_store: BundledAppsStore,
 }
 
 impl BundleStore {
     fn new(path: PathBuf) -> crate::Result<Self> {
         let installed_apps_store = InstalledAppsStore::new(path.join("installed-apps.json"))?;
+        let notifications_settings_store =
+            NotificationsSettingsStore::new(path.join("notifications-settings.json"))?;
+        let apps_awaiting_membrane_proofs_store =
+            AppsAwaitingMembraneProofsStore::new(path.join("apps-awaiting-membrane-proofs.json"))?;
+        let host_labels_store = HostLabelsStore::new(path.join("host-labels.json"))?;
+        let applied_releases_store = AppliedReleasesStore::new(path.join("applied-releases.json"))?;
+        let bundled_apps_store = BundledAppsStore::new(path.join("bundled-apps.json"))?;
 
         let bundle_store = BundleStore {
             path,
             installed_apps_store,
+            notifications_settings_store,
+            apps_awaiting_membrane_proofs_store,
+            host_labels_store,
+            applied_releases_store,
+            bundled_apps_store,
         };
         fs::create_dir_all(bundle_store.happ_bundle_store().path)?;
         fs::create_dir_all(bundle_store.ui_store().path)?;
+        bundle_store.add_missing_host_labels()?;
 
         Ok(bundle_store)
     }
@@ -95,23 +122,200 @@ impl BundleStore {
         Ok(path)
     }
 
+    /// Decodes the host label of a `happ://` origin back to the id of the installed app it belongs to
+    ///
+    /// * `host_label` - the host label, as built by `crate::origin::app_id_host_label()`
+    pub fn get_app_id_for_host_label(
+        &self,
+        host_label: &str,
+    ) -> crate::Result<Option<InstalledAppId>> {
+        let app_id = self.host_labels_store.get()?.get(host_label).cloned();
+
+        Ok(app_id)
+    }
+
+    /// Fails if the host label for the given app id already belongs to another app,
+    /// since both apps would be served from the same `happ://` origin
+    pub(crate) fn check_host_label_available(&self, app_id: &InstalledAppId) -> crate::Result<()> {
+        match self
+            .host_labels_store
+            .get()?
+            .get(&app_id_host_label(app_id))
+        {
+            Some(other_app_id) if !other_app_id.eq(app_id) => Err(crate::Error::HostLabelTaken {
+                app_id: app_id.clone(),
+                other_app_id: other_app_id.clone(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn add_host_label(&self, app_id: &InstalledAppId) -> crate::Result<()> {
+        let host_label = app_id_host_label(app_id);
+        if self.host_labels_store.get()?.contains_key(&host_label) {
+            return Ok(());
+        }
+
+        self.host_labels_store.update(|host_labels| {
+            host_labels.insert(host_label.clone(), app_id.clone());
+        })
+    }
+
+    /// Adds the host labels of the apps stored before they were kept in the host labels store
+    fn add_missing_host_labels(&self) -> crate::Result<()> {
+        let mut app_ids: Vec<InstalledAppId> =
+            self.installed_apps_store.get()?.keys().cloned().collect();
+        app_ids.extend(
+            self.apps_awaiting_membrane_proofs_store
+                .get()?
+                .keys()
+                .cloned(),
+        );
+
+        for app_id in app_ids {
+            self.add_host_label(&app_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Stores the given bundle as the one the app is installed with, keeping the UI of web-apps
     pub fn store_happ_bundle(
         &self,
         app_id: InstalledAppId,
         app_bundle: &AppBundle,
     ) -> crate::Result<()> {
+        let mut installed_app_info = self.store_happ_bundle_files(app_bundle)?;
+        installed_app_info.web_app_info = self
+            .installed_apps_store
+            .get()?
+            .get(&app_id)
+            .and_then(|installed_app_info| installed_app_info.web_app_info.clone());
+
+        self.add_installed_app(app_id, installed_app_info)
+    }
+
+    /// Stores the given bundle without adding it to the installed apps, returning the entry to add once it's installed
+    pub(crate) fn store_happ_bundle_files(
+        &self,
+        app_bundle: &AppBundle,
+    ) -> crate::Result<InstalledAppInfo> {
         let happ_bundle_hash = self.happ_bundle_store().store_app_bundle(&app_bundle)?;
+
+        Ok(InstalledAppInfo {
+            happ_bundle_hash,
+            web_app_info: None,
+        })
+    }
+
+    /// Adds the given app to the installed apps, so that its bundle and UI can be found from its id
+    pub(crate) fn add_installed_app(
+        &self,
+        app_id: InstalledAppId,
+        installed_app_info: InstalledAppInfo,
+    ) -> crate::Result<()> {
+        self.add_host_label(&app_id)?;
         self.installed_apps_store.update(|installed_apps| {
-            installed_apps.insert(
-                app_id.clone(),
-                InstalledAppInfo {
-                    happ_bundle_hash: happ_bundle_hash.clone(),
-                    web_app_info: None,
-                },
-            );
-        })?;
+            installed_apps.insert(app_id.clone(), installed_app_info.clone());
+        })
+    }
 
-        Ok(())
+    /// Returns the hApp bundle that the given app was installed or last updated with, if it was stored
+    pub fn get_happ_bundle(&self, app_id: &InstalledAppId) -> crate::Result<Option<AppBundle>> {
+        let installed_apps = self.installed_apps_store.get()?;
+        let Some(installed_app_info) = installed_apps.get(app_id) else {
+            return Ok(None);
+        };
+
+        self.happ_bundle_store()
+            .get_app_bundle(&installed_app_info.happ_bundle_hash)
+    }
+
+    /// Holds the given app until its membrane proofs are provided, storing the bundle it will be installed with
+    ///
+    /// * `app_bundle` - the bundle for the app, with the network seed and the role options already applied
+    /// * `installed_app_info` - the entry to add to the installed apps once the app is installed
+    pub(crate) fn store_app_awaiting_membrane_proofs(
+        &self,
+        app_id: InstalledAppId,
+        app_bundle: &AppBundle,
+        agent_pub_key: AgentPubKey,
+        installed_app_info: InstalledAppInfo,
+    ) -> crate::Result<()> {
+        let happ_bundle_hash = self.happ_bundle_store().store_app_bundle(app_bundle)?;
+        self.add_host_label(&app_id)?;
+        self.apps_awaiting_membrane_proofs_store
+            .update(|apps_awaiting_membrane_proofs| {
+                apps_awaiting_membrane_proofs.insert(
+                    app_id.clone(),
+                    AppAwaitingMembraneProofs {
+                        happ_bundle_hash: happ_bundle_hash.clone(),
+                        agent_pub_key: agent_pub_key.clone(),
+                        installed_app_info: installed_app_info.clone(),
+                    },
+                );
+            })
+    }
+
+    /// Returns the app held until its membrane proofs are provided, along with the bundle to install it with
+    pub(crate) fn get_app_awaiting_membrane_proofs(
+        &self,
+        app_id: &InstalledAppId,
+    ) -> crate::Result<Option<(AppAwaitingMembraneProofs, AppBundle)>> {
+        let Some(awaiting) = self
+            .apps_awaiting_membrane_proofs_store
+            .get()?
+            .get(app_id)
+            .cloned()
+        else {
+            return Ok(None);
+        };
+
+        let Some(app_bundle) = self
+            .happ_bundle_store()
+            .get_app_bundle(&awaiting.happ_bundle_hash)?
+        else {
+            return Err(crate::Error::AppDoesNotExist(app_id.clone()));
+        };
+
+        Ok(Some((awaiting, app_bundle)))
+    }
+
+    /// Stops holding the given app, returning whether it was awaiting its membrane proofs
+    pub(crate) fn remove_app_awaiting_membrane_proofs(
+        &self,
+        app_id: &InstalledAppId,
+    ) -> crate::Result<bool> {
+        let was_awaiting = self
+            .apps_awaiting_membrane_proofs_store
+            .get()?
+            .contains_key(app_id);
+        if was_awaiting {
+            self.apps_awaiting_membrane_proofs_store
+                .update(|apps_awaiting_membrane_proofs| {
+                    apps_awaiting_membrane_proofs.remove(app_id);
+                })?;
+        }
+
+        Ok(was_awaiting)
+    }
+
+    /// Forgets the given app, so that its UI can't be served anymore and its host label is available again
+    ///
+    /// The stored bundles and UIs are kept, since other apps may have been installed from them
+    pub fn remove_app(&self, app_id: &InstalledAppId) -> crate::Result<()> {
+        self.host_labels_store.update(|host_labels| {
+            host_labels.retain(|_, label_app_id| !label_app_id.eq(app_id));
+        })?;
+        self.applied_releases_store.update(|applied_releases| {
+            applied_releases.remove(app_id);
+        })?;
+        self.bundled_apps_store.update(|bundled_apps| {
+            bundled_apps.remove(app_id);
+        })?;
+        self.installed_apps_store.update(|installed_apps| {
+            installed_apps.remove(app_id);
+        })
     }
 
     pub fn web_app_bundle_hash(web_app_bundle: &WebAppBundle) -> crate::Result<String> {
@@ -124,6 +328,16 @@ impl BundleStore {
         app_id: InstalledAppId,
         web_app_bundle: &WebAppBundle,
     ) -> crate::Result<()> {
+        let installed_app_info = self.store_web_happ_bundle_files(web_app_bundle).await?;
+        self.add_installed_app(app_id, installed_app_info)
+    }
+
+    /// Stores the given bundle and extracts its UI without adding it to the installed apps,
+    /// returning the entry to add once it's installed
+    pub(crate) async fn store_web_happ_bundle_files(
+        &self,
+        web_app_bundle: &WebAppBundle,
+    ) -> crate::Result<InstalledAppInfo> {
         let web_happ_bundle_hash = Self::web_app_bundle_hash(&web_app_bundle)?;
 
         let happ_bundle = web_app_bundle.happ_bundle().await?;
@@ -133,28 +347,23 @@ impl BundleStore {
             .extract_and_store_ui(&web_app_bundle)
             .await?;
 
-        self.installed_apps_store.update(move |installed_apps| {
-            installed_apps.insert(
-                app_id.clone(),
-                InstalledAppInfo {
-                    happ_bundle_hash: happ_bundle_hash.clone(),
-                    web_app_info: Some(InstalledWebAppInfo {
-                        web_happ_bundle_hash: web_happ_bundle_hash.clone(),
-                        ui_hash: ui_hash.clone(),
-                    }),
-                },
-            );
+        Ok(InstalledAppInfo {
+            happ_bundle_hash,
+            web_app_info: Some(InstalledWebAppInfo {
+                web_happ_bundle_hash,
+                ui_hash,
+            }),
         })
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct InstalledWebAppInfo {
     pub ui_hash: String,
     pub web_happ_bundle_hash: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct InstalledAppInfo {
     pub happ_bundle_hash: String,
     pub web_app_info: Option<InstalledWebAppInfo>,
@@ -162,53 +371,59 @@ pub struct InstalledAppInfo {
 
 pub type InstalledAppsInfo = HashMap<String, InstalledAppInfo>;
 
-pub struct InstalledAppsStore {
+pub type InstalledAppsStore = JsonStore<InstalledAppsInfo>;
+
+/// The app ids of the apps stored in the bundle store, by the host label of their `happ://` origin
+type HostLabelsStore = JsonStore<HashMap<String, InstalledAppId>>;
+
+/// Value persisted in a JSON file, and kept in memory for fast access
+pub struct JsonStore<T> {
     json_config_path: PathBuf,
-    installed_apps: std::sync::RwLock<InstalledAppsInfo>,
+    value: std::sync::RwLock<T>,
 }
 
-impl InstalledAppsStore {
+impl<T: Serialize + DeserializeOwned + Default> JsonStore<T> {
     fn new(json_config_path: PathBuf) -> crate::Result<Self> {
-        let apps = if json_config_path.exists() {
+        let value = if json_config_path.exists() {
             let s = std::fs::read_to_string(json_config_path.clone())?;
 
-            let apps: HashMap<String, InstalledAppInfo> = serde_json::from_str(s.as_str())?;
-            apps
+            let value: T = serde_json::from_str(s.as_str())?;
+            value
         } else {
             let mut file = std::fs::File::create(json_config_path.clone())?;
 
-            let apps: HashMap<String, InstalledAppInfo> = HashMap::new();
+            let value = T::default();
 
-            let data = serde_json::to_string(&apps)?;
+            let data = serde_json::to_string(&value)?;
 
             file.write(&data.as_bytes())?;
-            apps
+            value
         };
 
         Ok(Self {
             json_config_path,
-            installed_apps: std::sync::RwLock::new(apps),
+            value: std::sync::RwLock::new(value),
         })
     }
 
-    pub fn get<'a>(&'a self) -> crate::Result<RwLockReadGuard<'a, InstalledAppsInfo>> {
-        self.installed_apps
+    pub fn get<'a>(&'a self) -> crate::Result<RwLockReadGuard<'a, T>> {
+        self.value
             .read()
             .map_err(|err| crate::Error::LockError(format!("{err:?}")))
     }
 
     pub fn update<F>(&self, update_fn: F) -> crate::Result<()>
     where
-        F: Fn(&mut InstalledAppsInfo) -> (),
+        F: Fn(&mut T) -> (),
     {
         let mut write_lock = self
-            .installed_apps
+            .value
             .write()
             .map_err(|err| crate::Error::LockError(format!("{err:?}")))?;
 
         update_fn(&mut write_lock);
 
-        let data = serde_json::to_string(&write_lock.clone())?;
+        let data = serde_json::to_string(&*write_lock)?;
 
         std::fs::write(self.json_config_path.clone(), data)?;
 
@@ -233,6 +448,11 @@ pub struct UiStore {
 }
 
 impl UiStore {
+    pub async fn ui_hash(web_app: &WebAppBundle) -> Result<String, FileSystemError> {
+        let ui_bytes = web_app.web_ui_zip_bytes().await?;
+        Ok(sha256::digest(ui_bytes.to_vec()))
+    }
+
     pub async fn extract_and_store_ui(
         &self,
         web_app: &WebAppBundle,
@@ -299,6 +519,18 @@ impl AppBundleStore {
     //     }
     // }
 
+    pub fn get_app_bundle(&self, happ_bundle_hash: &String) -> crate::Result<Option<AppBundle>> {
+        let path = self.path.join(format!("{}.happ", happ_bundle_hash));
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let bytes = fs::read(path)?;
+        let app_bundle = AppBundle::decode(bytes.as_slice())?;
+
+        Ok(Some(app_bundle))
+    }
+
     pub fn store_app_bundle(&self, app_bundle: &AppBundle) -> crate::Result<String> {
         let bytes = app_bundle.encode()?;
         let hash = sha256::digest(&bytes);
@@ -336,3 +568,119 @@ pub fn unzip_file(reader: std::fs::File, outpath: PathBuf) -> Result<(), FileSys
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_bundle() -> AppBundle {
+        let manifest: AppManifest = AppManifestV1 {
+            name: String::from("forum"),
+            description: None,
+            roles: vec![],
+        }
+        .into();
+        mr_bundle::Bundle::new_unchecked(manifest, vec![])
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn apps_awaiting_membrane_proofs_survive_a_restart() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let app_id = InstalledAppId::from("forum");
+        let agent_pub_key = AgentPubKey::from_raw_36(vec![0xdb; 36]);
+
+        let bundle_store = BundleStore::new(tmp_dir.path().to_path_buf()).unwrap();
+        let installed_app_info = bundle_store.store_happ_bundle_files(&app_bundle()).unwrap();
+        bundle_store
+            .store_app_awaiting_membrane_proofs(
+                app_id.clone(),
+                &app_bundle(),
+                agent_pub_key.clone(),
+                installed_app_info.clone(),
+            )
+            .unwrap();
+        // It's not installed until its membrane proofs are provided
+        assert!(!bundle_store
+            .installed_apps_store
+            .get()
+            .unwrap()
+            .contains_key(&app_id));
+        drop(bundle_store);
+
+        // The runtime is restarted before the membrane proofs are provided
+        let bundle_store = BundleStore::new(tmp_dir.path().to_path_buf()).unwrap();
+        let (awaiting, restored_bundle) = bundle_store
+            .get_app_awaiting_membrane_proofs(&app_id)
+            .unwrap()
+            .expect("the app should still be awaiting its membrane proofs");
+
+        assert_eq!(awaiting.agent_pub_key, agent_pub_key);
+        assert_eq!(awaiting.installed_app_info, installed_app_info);
+        assert_eq!(restored_bundle.manifest(), app_bundle().manifest());
+
+        assert!(bundle_store
+            .remove_app_awaiting_membrane_proofs(&app_id)
+            .unwrap());
+        let bundle_store = BundleStore::new(tmp_dir.path().to_path_buf()).unwrap();
+        assert!(bundle_store
+            .get_app_awaiting_membrane_proofs(&app_id)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn storing_the_happ_bundle_of_a_web_app_keeps_its_ui() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let app_id = InstalledAppId::from("forum");
+        let web_app_info = InstalledWebAppInfo {
+            ui_hash: String::from("ui"),
+            web_happ_bundle_hash: String::from("web-happ"),
+        };
+
+        let bundle_store = BundleStore::new(tmp_dir.path().to_path_buf()).unwrap();
+        let mut installed_app_info = bundle_store.store_happ_bundle_files(&app_bundle()).unwrap();
+        installed_app_info.web_app_info = Some(web_app_info.clone());
+        bundle_store
+            .add_installed_app(app_id.clone(), installed_app_info)
+            .unwrap();
+
+        bundle_store
+            .store_happ_bundle(app_id.clone(), &app_bundle())
+            .unwrap();
+
+        let installed_apps = bundle_store.installed_apps_store.get().unwrap();
+        assert_eq!(installed_apps[&app_id].web_app_info, Some(web_app_info));
+    }
+
+    #[test]
+    fn host_labels_resolve_to_the_app_that_owns_them() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let app_id = InstalledAppId::from("My App");
+        let host_label = app_id_host_label(&app_id);
+
+        let bundle_store = BundleStore::new(tmp_dir.path().to_path_buf()).unwrap();
+        bundle_store
+            .store_happ_bundle(app_id.clone(), &app_bundle())
+            .unwrap();
+        assert_eq!(
+            bundle_store.get_app_id_for_host_label(&host_label).unwrap(),
+            Some(app_id.clone())
+        );
+
+        // An app id equal to the encoded label of another app can't take over its origin
+        assert!(matches!(
+            bundle_store.check_host_label_available(&host_label),
+            Err(crate::Error::HostLabelTaken { .. })
+        ));
+        assert!(bundle_store.check_host_label_available(&app_id).is_ok());
+
+        bundle_store.remove_app(&app_id).unwrap();
+        assert_eq!(
+            bundle_store.get_app_id_for_host_label(&host_label).unwrap(),
+            None
+        );
+        assert!(bundle_store.check_host_label_available(&host_label).is_ok());
+    }
+}
@@ -0,0 +1,484 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, PoisonError},
+    time::{Duration, Instant},
+};
+
+use holochain_client::InstalledAppId;
+use serde::Serialize;
+use tauri::{EventTarget, Manager, Runtime};
+
+use crate::{launch::start_conductor, AdminPort, AppWebsocketAuth, HolochainExt, HolochainPlugin};
+
+/// Event emitted by the health monitor with a `HolochainHealth` payload,
+/// whenever the status of the conductor changes or a check fails
+pub const HOLOCHAIN_HEALTH_EVENT: &'static str = "holochain-health";
+
+/// Event emitted to the windows of an app with an `AppWebsocketAuth` payload after the conductor was restarted
+/// or the app interface they were connected through was detached,
+/// since the app websocket port and auth token they were opened with are not valid anymore
+pub const APP_WEBSOCKET_AUTH_REISSUED_EVENT: &'static str = "app-websocket-auth-reissued";
+
+/// How often to check whether the previous conductor was released while restarting it
+const RELEASE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How the health of the conductor is monitored in the background
+///
+/// By default the conductor is only monitored, set `restart_after_failures` to also restart it when it's unhealthy
+#[derive(Debug, Clone)]
+pub struct HealthMonitorConfig {
+    /// How often to probe the admin websocket and the conductor state
+    pub check_interval: Duration,
+    /// Restart the conductor after this many consecutive failed checks, `None` (the default) to never restart it
+    ///
+    /// The windows of the apps get the new app websocket port and auth token
+    /// with the `app-websocket-auth-reissued` event after the restart
+    pub restart_after_failures: Option<u32>,
+}
+
+impl Default for HealthMonitorConfig {
+    fn default() -> Self {
+        HealthMonitorConfig {
+            check_interval: Duration::from_secs(30),
+            restart_after_failures: None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    #[default]
+    Healthy,
+    /// The last check failed
+    Unhealthy,
+    /// The conductor is being restarted after too many failed checks
+    Restarting,
+}
+
+/// Payload of the `holochain-health` event
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct HolochainHealth {
+    pub status: HealthStatus,
+    pub consecutive_failures: u32,
+    /// The error of the last failed check or restart, kept until a check succeeds
+    pub last_error: Option<String>,
+}
+
+/// The health after a check, and what the health monitor needs to do about it
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HealthCheckOutcome {
+    health: HolochainHealth,
+    /// Whether the new health needs to be emitted
+    emit: bool,
+    /// Whether the conductor needs to be restarted
+    restart: bool,
+}
+
+/// Computes the health after a check with the given result
+///
+/// * `restart_after_failures` - as in `HealthMonitorConfig::restart_after_failures`
+fn health_after_check(
+    previous: &HolochainHealth,
+    check: Result<(), String>,
+    restart_after_failures: Option<u32>,
+) -> HealthCheckOutcome {
+    let mut health = match check {
+        Ok(()) => HolochainHealth::default(),
+        Err(err) => HolochainHealth {
+            status: HealthStatus::Unhealthy,
+            consecutive_failures: previous.consecutive_failures + 1,
+            last_error: Some(err),
+        },
+    };
+
+    let restart = restart_after_failures
+        .is_some_and(|max_failures| health.consecutive_failures >= max_failures);
+    if restart {
+        health.status = HealthStatus::Restarting;
+    }
+    let emit = health.status != previous.status || health.status != HealthStatus::Healthy;
+
+    HealthCheckOutcome {
+        health,
+        emit,
+        restart,
+    }
+}
+
+/// Waits until the given value is not referenced anywhere else, so that dropping it releases what it holds
+///
+/// Fails with `Error::ConductorNotReleased` if it's still referenced after `timeout`
+async fn wait_until_released<T>(value: &Arc<T>, timeout: Duration) -> crate::Result<()> {
+    let started_at = Instant::now();
+    while Arc::strong_count(value) > 1 {
+        if started_at.elapsed() >= timeout {
+            return Err(crate::Error::ConductorNotReleased { timeout });
+        }
+        async_std::task::sleep(RELEASE_POLL_INTERVAL).await;
+    }
+    Ok(())
+}
+
+/// Waits until the conductor being released is not referenced anywhere else, and drops it
+///
+/// If it's still referenced after `timeout`, it's kept in `releasing` so that the next restart waits for it again
+async fn release_conductor<T>(
+    releasing: &mut Option<Arc<T>>,
+    timeout: Duration,
+) -> crate::Result<()> {
+    if let Some(conductor) = releasing.as_ref() {
+        wait_until_released(conductor, timeout).await?;
+    }
+    *releasing = None;
+    Ok(())
+}
+
+/// Computes the health after restarting the conductor, which is always emitted
+fn health_after_restart(
+    previous: &HolochainHealth,
+    restart: Result<(), String>,
+) -> HolochainHealth {
+    match restart {
+        Ok(()) => HolochainHealth::default(),
+        Err(err) => HolochainHealth {
+            status: HealthStatus::Unhealthy,
+            consecutive_failures: previous.consecutive_failures,
+            last_error: Some(err),
+        },
+    }
+}
+
+impl<R: Runtime> HolochainPlugin<R> {
+    /// The health of the conductor as of the last check of the health monitor
+    pub async fn health(&self) -> HolochainHealth {
+        self.holochain_runtime.health.lock().await.clone()
+    }
+
+    /// Spawns the health monitor, if it's enabled in `HolochainPluginConfig::health_monitor`
+    pub(crate) fn start_health_monitor(&self) {
+        let Some(health_monitor) = self.config.health_monitor.clone() else {
+            return;
+        };
+        let app_handle = self.app_handle.clone();
+
+        tauri::async_runtime::spawn(async move {
+            loop {
+                async_std::task::sleep(health_monitor.check_interval).await;
+
+                let Ok(holochain) = app_handle.holochain() else {
+                    return;
+                };
+                holochain.check_health(&health_monitor).await;
+            }
+        });
+    }
+
+    async fn check_health(&self, health_monitor: &HealthMonitorConfig) {
+        let check = self.probe_conductor().await.map_err(|err| {
+            log::warn!("Holochain health check failed: {err:?}");
+            err.to_string()
+        });
+
+        let mut health = self.holochain_runtime.health.lock().await;
+        let outcome = health_after_check(&health, check, health_monitor.restart_after_failures);
+        *health = outcome.health;
+        if outcome.emit {
+            self.emit_health(&health);
+        }
+        drop(health);
+
+        if !outcome.restart {
+            return;
+        }
+
+        let restart = self.restart_conductor().await.map_err(|err| {
+            log::error!("Failed to restart the conductor: {err:?}");
+            err.to_string()
+        });
+        let restarted = restart.is_ok();
+
+        let mut health = self.holochain_runtime.health.lock().await;
+        *health = health_after_restart(&health, restart);
+        self.emit_health(&health);
+        drop(health);
+
+        if restarted {
+            log::info!("Restarted the conductor");
+            self.reissue_app_websocket_auths().await;
+        }
+    }
+
+    /// Checks that the admin websocket is reachable and the conductor answers requests
+    async fn probe_conductor(&self) -> crate::Result<()> {
+        let admin_ws = self.admin_websocket().await?;
+        admin_ws
+            .list_apps(None)
+            .await
+            .map_err(|err| crate::Error::ConductorApiError(err))?;
+        Ok(())
+    }
+
+    /// Shuts down the conductor and builds it again with the same configuration and admin port
+    ///
+    /// The new conductor is only built once the old one is dropped, since they share the keystore and the databases
+    async fn restart_conductor(&self) -> crate::Result<()> {
+        log::warn!("Restarting the conductor");
+        let old_conductor = self
+            .holochain_runtime
+            .conductor_handle
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+
+        let mut releasing_conductor = self.holochain_runtime.releasing_conductor.lock().await;
+        // Already gone if a previous restart failed
+        if let Some(old_conductor) = old_conductor {
+            let shutdown = old_conductor.shutdown().await;
+            log::info!("Shut down the conductor: {shutdown:?}");
+            *releasing_conductor = Some(old_conductor);
+        }
+
+        // Requests that were already being handled may still hold the old conductor for a moment,
+        // and a previous restart may have given up waiting for it
+        release_conductor(&mut releasing_conductor, self.config.startup_timeout).await?;
+        drop(releasing_conductor);
+
+        // Same admin port as before, so that it stays reachable where it was
+        let (conductor_handle, _admin_port) = start_conductor(
+            &self.holochain_runtime.filesystem,
+            &AdminPort::Fixed(self.holochain_runtime.admin_port),
+            self.holochain_runtime.passphrase.clone(),
+            &self.config,
+        )
+        .await?;
+        *self
+            .holochain_runtime
+            .conductor_handle
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(conductor_handle);
+
+        // The auth tokens of the previous conductor are not valid anymore, and its app interfaces need to be loaded again
+        self.clear_app_websocket_auths().await;
+        self.load_app_interfaces().await?;
+
+        Ok(())
+    }
+
+    /// Forgets all the app websocket ports and auth tokens of the previous conductor
+    async fn clear_app_websocket_auths(&self) {
+        self.holochain_runtime
+            .apps_websockets_auths
+            .lock()
+            .await
+            .clear();
+        self.holochain_runtime
+            .issued_app_websocket_auths
+            .lock()
+            .await
+            .clear();
+        self.holochain_runtime.app_interfaces.lock().await.clear();
+        self.holochain_runtime.apps_websockets.lock().await.clear();
+    }
+
+    /// Issues new auth tokens for the apps with open windows, and sends them to their windows
+    async fn reissue_app_websocket_auths(&self) {
+        let app_ids: HashSet<InstalledAppId> = self
+            .holochain_runtime
+            .apps_windows
+            .lock()
+            .await
+            .values()
+            .cloned()
+            .collect();
+
+        for app_id in app_ids {
+            let app_websocket_auth = match self.get_app_websocket_auth(&app_id).await {
+                Ok(app_websocket_auth) => app_websocket_auth,
+                Err(err) => {
+                    log::error!("Failed to reissue the app auth token for app {app_id}: {err:?}");
+                    continue;
+                }
+            };
+
+            for label in self.get_windows_for_app(&app_id).await {
+                self.send_app_websocket_auth_to_window(label, &app_websocket_auth);
+            }
+        }
+    }
+
+    /// Updates the launcher environment of the window, so that clients created from now on use the new auth,
+    /// and emits the `app-websocket-auth-reissued` event for the clients that are already connected
+    pub(crate) fn send_app_websocket_auth_to_window(
+        &self,
+        label: String,
+        app_websocket_auth: &AppWebsocketAuth,
+    ) {
+        if let Some(window) = self.app_handle.get_webview_window(&label) {
+            let token: Vec<String> = app_websocket_auth
+                .token
+                .iter()
+                .map(|n| n.to_string())
+                .collect();
+            let script = format!(
+                r#"
+            if (!window.__HC_LAUNCHER_ENV__) window.__HC_LAUNCHER_ENV__ = {{}};
+            window.__HC_LAUNCHER_ENV__.APP_INTERFACE_PORT = {};
+            window.__HC_LAUNCHER_ENV__.APP_INTERFACE_TOKEN = [{}];
+            window.__HC_LAUNCHER_ENV__.APP_INTERFACE_TOKEN_EXPIRES_AT = {};
+        "#,
+                app_websocket_auth.app_websocket_port,
+                token.join(","),
+                app_websocket_auth.expires_at_millis()
+            );
+            if let Err(err) = window.eval(&script) {
+                log::error!("Failed to update the launcher environment of window {label}: {err:?}");
+            }
+        }
+
+        if let Err(err) = self.app_handle.emit_to(
+            EventTarget::AnyLabel { label },
+            APP_WEBSOCKET_AUTH_REISSUED_EVENT,
+            app_websocket_auth.clone(),
+        ) {
+            log::error!("Failed to emit the reissued app auth token: {err:?}");
+        }
+    }
+
+    fn emit_health(&self, health: &HolochainHealth) {
+        if let Err(err) = self.app_handle.emit(HOLOCHAIN_HEALTH_EVENT, health.clone()) {
+            log::error!("Failed to emit the holochain health: {err:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unhealthy(consecutive_failures: u32) -> HolochainHealth {
+        HolochainHealth {
+            status: HealthStatus::Unhealthy,
+            consecutive_failures,
+            last_error: Some(String::from("connection refused")),
+        }
+    }
+
+    #[test]
+    fn successful_checks_only_emit_when_recovering() {
+        let outcome = health_after_check(&HolochainHealth::default(), Ok(()), Some(3));
+        assert_eq!(outcome.health, HolochainHealth::default());
+        assert!(!outcome.emit);
+        assert!(!outcome.restart);
+
+        let outcome = health_after_check(&unhealthy(2), Ok(()), Some(3));
+        assert_eq!(outcome.health, HolochainHealth::default());
+        assert!(outcome.emit);
+        assert!(!outcome.restart);
+    }
+
+    #[test]
+    fn failed_checks_are_counted_and_emitted() {
+        let error = String::from("connection refused");
+
+        let outcome = health_after_check(&HolochainHealth::default(), Err(error.clone()), Some(3));
+        assert_eq!(outcome.health, unhealthy(1));
+        assert!(outcome.emit);
+        assert!(!outcome.restart);
+
+        let outcome = health_after_check(&unhealthy(1), Err(error), Some(3));
+        assert_eq!(outcome.health, unhealthy(2));
+        assert!(outcome.emit);
+        assert!(!outcome.restart);
+    }
+
+    #[test]
+    fn restarts_only_when_enabled_and_after_enough_failures() {
+        let error = String::from("connection refused");
+
+        let outcome = health_after_check(&unhealthy(2), Err(error.clone()), Some(3));
+        assert_eq!(outcome.health.status, HealthStatus::Restarting);
+        assert_eq!(outcome.health.consecutive_failures, 3);
+        assert!(outcome.emit);
+        assert!(outcome.restart);
+
+        let outcome = health_after_check(&unhealthy(99), Err(error), None);
+        assert_eq!(outcome.health, unhealthy(100));
+        assert!(!outcome.restart);
+    }
+
+    #[test]
+    fn waits_until_the_other_references_are_dropped() {
+        let conductor = Arc::new(());
+        let request = conductor.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            drop(request);
+        });
+
+        let released =
+            tauri::async_runtime::block_on(wait_until_released(&conductor, Duration::from_secs(5)));
+
+        assert!(released.is_ok());
+        assert_eq!(Arc::strong_count(&conductor), 1);
+    }
+
+    #[test]
+    fn fails_if_the_other_references_are_not_dropped_in_time() {
+        let conductor = Arc::new(());
+        let _request = conductor.clone();
+
+        let released = tauri::async_runtime::block_on(wait_until_released(
+            &conductor,
+            Duration::from_millis(100),
+        ));
+
+        assert!(matches!(
+            released,
+            Err(crate::Error::ConductorNotReleased { .. })
+        ));
+    }
+
+    #[test]
+    fn keeps_the_conductor_until_it_is_released() {
+        let conductor = Arc::new(());
+        let request = conductor.clone();
+        let mut releasing = Some(conductor);
+
+        let released = tauri::async_runtime::block_on(release_conductor(
+            &mut releasing,
+            Duration::from_millis(100),
+        ));
+        assert!(matches!(
+            released,
+            Err(crate::Error::ConductorNotReleased { .. })
+        ));
+        // Still there for the next restart to wait on it
+        assert!(releasing.is_some());
+
+        drop(request);
+        let released = tauri::async_runtime::block_on(release_conductor(
+            &mut releasing,
+            Duration::from_millis(100),
+        ));
+        assert!(released.is_ok());
+        assert!(releasing.is_none());
+    }
+
+    #[test]
+    fn failed_restarts_keep_the_conductor_unhealthy() {
+        let restarting = HolochainHealth {
+            status: HealthStatus::Restarting,
+            ..unhealthy(3)
+        };
+
+        assert_eq!(
+            health_after_restart(&restarting, Ok(())),
+            HolochainHealth::default()
+        );
+        assert_eq!(
+            health_after_restart(&restarting, Err(String::from("connection refused"))),
+            unhealthy(3)
+        );
+    }
+}
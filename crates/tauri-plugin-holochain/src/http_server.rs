@@ -9,6 +9,10 @@
 // use tauri::{AppHandle, Runtime};
 // use tokio::net::TcpListener;
 
+use std::path::{Component, Path, PathBuf};
+
+use percent_encoding::percent_decode_str;
+
 use crate::filesystem::FileSystem;
 
 pub fn pong_iframe() -> String {
@@ -151,21 +155,14 @@ pub fn pong_iframe() -> String {
 pub async fn read_asset(
     fs: &FileSystem,
     app_id: &String,
-    mut asset_name: String,
+    asset_name: String,
 ) -> crate::Result<Option<(Vec<u8>, Option<String>)>> {
     log::debug!("Reading asset from filesystem. Asset name: {}", asset_name);
-    if asset_name.starts_with("/") {
-        asset_name = asset_name
-            .strip_prefix("/")
-            .expect("Failed to strip prefix")
-            .to_string();
-    }
-    if asset_name == "" {
-        asset_name = String::from("index.html");
-    }
 
     let assets_path = fs.bundle_store.get_ui_path(&app_id)?;
-    let asset_file = assets_path.join(asset_name);
+    let Some(asset_file) = resolve_asset_path(&assets_path, &asset_name)? else {
+        return Ok(None);
+    };
 
     let mime_guess = mime_guess::from_path(asset_file.clone());
 
@@ -182,3 +179,202 @@ pub async fn read_asset(
         Err(_e) => Ok(None),
     }
 }
+
+/// Splits the given `happ://` URI into the host label of the app and the path of the requested asset
+///
+/// The path is percent-decoded, so that encoded separators (e.g. `..%2f..`) are rejected by `resolve_asset_path()`
+/// like any other `..` component. Returns `None` if the URI has no host label or its path is not valid UTF-8
+pub fn parse_happ_uri(uri: &str) -> Option<(String, String)> {
+    let (_protocol, uri_without_protocol) = uri.split_once("://")?;
+    let uri_without_querystring = uri_without_protocol
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default();
+    let (host_label, asset_path) = uri_without_querystring
+        .split_once('/')
+        .unwrap_or((uri_without_querystring, ""));
+    if host_label.is_empty() {
+        return None;
+    }
+
+    let asset_path = percent_decode_str(asset_path).decode_utf8().ok()?;
+    Some((host_label.to_string(), asset_path.into_owned()))
+}
+
+/// Resolves the given asset name to a canonical path inside the `ui_root` directory
+///
+/// Returns `Ok(None)` if the asset does not exist, and `Error::ForbiddenAssetPath` if the asset name
+/// contains `..` or absolute components, or if it resolves (e.g. through a symlink) to a path outside of `ui_root`
+pub fn resolve_asset_path(ui_root: &Path, asset_name: &str) -> crate::Result<Option<PathBuf>> {
+    let mut asset_name = asset_name.strip_prefix("/").unwrap_or(asset_name);
+    if asset_name == "" {
+        asset_name = "index.html";
+    }
+
+    let mut relative_path = PathBuf::new();
+    for component in Path::new(asset_name).components() {
+        match component {
+            Component::Normal(c) => relative_path.push(c),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(crate::Error::ForbiddenAssetPath(asset_name.to_string()));
+            }
+        }
+    }
+
+    let canonical_root = ui_root.canonicalize()?;
+    let asset_path = canonical_root.join(relative_path);
+
+    if !asset_path.exists() {
+        return Ok(None);
+    }
+
+    let canonical_asset_path = asset_path.canonicalize()?;
+    if !canonical_asset_path.starts_with(&canonical_root) {
+        return Err(crate::Error::ForbiddenAssetPath(asset_name.to_string()));
+    }
+
+    Ok(Some(canonical_asset_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixture {
+        _tmp_dir: tempfile::TempDir,
+        ui_root: PathBuf,
+    }
+
+    /// Builds a UI folder next to a folder with "secret" files that must never be served
+    fn fixture() -> Fixture {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let ui_root = tmp_dir.path().join("uis").join("ui-hash");
+        let other = tmp_dir.path().join("keystore");
+
+        std::fs::create_dir_all(ui_root.join("assets")).unwrap();
+        std::fs::create_dir_all(&other).unwrap();
+        std::fs::write(ui_root.join("index.html"), "<html></html>").unwrap();
+        std::fs::write(ui_root.join("assets").join("index.js"), "").unwrap();
+        std::fs::write(other.join("store_file"), "secret").unwrap();
+        symlink::symlink_file(other.join("store_file"), ui_root.join("escape.txt")).unwrap();
+        symlink::symlink_dir(&other, ui_root.join("escape-dir")).unwrap();
+
+        Fixture {
+            _tmp_dir: tmp_dir,
+            ui_root,
+        }
+    }
+
+    fn assert_forbidden(ui_root: &Path, asset_name: &str) {
+        assert!(
+            matches!(
+                resolve_asset_path(ui_root, asset_name),
+                Err(crate::Error::ForbiddenAssetPath(_))
+            ),
+            "{asset_name} should have been rejected"
+        );
+    }
+
+    #[test]
+    fn resolves_assets_inside_the_ui_root() {
+        let f = fixture();
+        let root = f.ui_root.canonicalize().unwrap();
+
+        assert_eq!(
+            resolve_asset_path(&f.ui_root, "").unwrap(),
+            Some(root.join("index.html"))
+        );
+        assert_eq!(
+            resolve_asset_path(&f.ui_root, "/").unwrap(),
+            Some(root.join("index.html"))
+        );
+        assert_eq!(
+            resolve_asset_path(&f.ui_root, "/assets/index.js").unwrap(),
+            Some(root.join("assets").join("index.js"))
+        );
+        assert_eq!(
+            resolve_asset_path(&f.ui_root, "./assets/./index.js").unwrap(),
+            Some(root.join("assets").join("index.js"))
+        );
+        assert_eq!(resolve_asset_path(&f.ui_root, "missing.js").unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_parent_dir_components() {
+        let f = fixture();
+
+        assert_forbidden(&f.ui_root, "../../keystore/store_file");
+        assert_forbidden(&f.ui_root, "/../ui-hash/index.html");
+        assert_forbidden(&f.ui_root, "assets/../../ui-hash/index.html");
+        assert_forbidden(&f.ui_root, "..");
+    }
+
+    #[test]
+    fn rejects_absolute_paths() {
+        let f = fixture();
+        let secret = f.ui_root.join("../../keystore/store_file");
+
+        assert_forbidden(&f.ui_root, "//etc/passwd");
+        assert_forbidden(&f.ui_root, secret.to_str().unwrap());
+    }
+
+    #[test]
+    fn parses_happ_uris() {
+        assert_eq!(
+            parse_happ_uri("happ://forum/assets/index.js?v=2#top"),
+            Some(("forum".into(), "assets/index.js".into()))
+        );
+        assert_eq!(
+            parse_happ_uri("happ://forum"),
+            Some(("forum".into(), "".into()))
+        );
+        assert_eq!(
+            parse_happ_uri("happ://forum/my%20file.txt"),
+            Some(("forum".into(), "my file.txt".into()))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_happ_uris() {
+        assert_eq!(parse_happ_uri("happ:///index.html"), None);
+        assert_eq!(parse_happ_uri("happ://"), None);
+        assert_eq!(parse_happ_uri("happ:forum/index.html"), None);
+        assert_eq!(parse_happ_uri("happ://forum/%ff"), None);
+    }
+
+    #[test]
+    fn hostile_happ_uris_stay_inside_the_ui_root() {
+        let f = fixture();
+        let asset_path = |uri: &str| parse_happ_uri(uri).unwrap().1;
+
+        assert_forbidden(
+            &f.ui_root,
+            &asset_path("happ://forum/..%2f..%2fkeystore%2fstore_file"),
+        );
+        assert_forbidden(&f.ui_root, &asset_path("happ://forum/..%2F.."));
+        assert_forbidden(
+            &f.ui_root,
+            &asset_path("happ://forum/%2e%2e/%2e%2e/keystore/store_file"),
+        );
+        assert_forbidden(&f.ui_root, &asset_path("happ://forum///etc/passwd"));
+        assert_forbidden(&f.ui_root, &asset_path("happ://forum/escape.txt"));
+        // A single leading slash is stripped, so the rest is resolved inside the UI root
+        assert_eq!(
+            resolve_asset_path(&f.ui_root, &asset_path("happ://forum//etc/passwd")).unwrap(),
+            None
+        );
+        assert_eq!(
+            resolve_asset_path(&f.ui_root, &asset_path("happ://forum/index.html?../..")).unwrap(),
+            Some(f.ui_root.canonicalize().unwrap().join("index.html"))
+        );
+    }
+
+    #[test]
+    fn rejects_symlinks_escaping_the_ui_root() {
+        let f = fixture();
+
+        assert_forbidden(&f.ui_root, "escape.txt");
+        assert_forbidden(&f.ui_root, "escape-dir/store_file");
+    }
+}
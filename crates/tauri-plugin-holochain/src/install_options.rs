@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use holochain::prelude::{
+    AppBundle, AppManifest, CellProvisioning, NetworkSeed, RoleName, Timestamp, YamlProperties,
+};
+use holochain_client::{AgentPubKey, InstalledAppId};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    filesystem::{InstalledAppInfo, JsonStore},
+    AgentKeySource,
+};
+
+/// Options to install an app with
+#[derive(Debug, Clone, Default)]
+pub struct InstallOptions {
+    /// Where to get the agent key for the app from
+    pub agent_key_source: AgentKeySource,
+    /// Network seed for all the roles that don't override it in `roles`
+    pub network_seed: Option<NetworkSeed>,
+    /// Overrides for the roles in the manifest of the app
+    pub roles: HashMap<RoleName, RoleOptions>,
+    /// Install the app without its membrane proofs, which need to be provided later with
+    /// `HolochainPlugin::provide_membrane_proofs()`
+    ///
+    /// The conductor needs the membrane proofs at install time, so the app is held by the plugin until then,
+    /// and it isn't listed among the installed apps before it is installed.
+    /// Held apps are persisted, so they still await their membrane proofs after the runtime is restarted
+    pub defer_membrane_proofs: bool,
+    /// Hex-encoded ed25519 signature by the publisher over the hash of the web-hApp bundle,
+    /// required by `HolochainPlugin::install_web_app()` if `HolochainPluginConfig::trusted_publishers` is not empty
+    pub publisher_signature: Option<String>,
+}
+
+/// Overrides for a role of the app being installed, applied to its manifest without repacking the bundle
+#[derive(Debug, Clone, Default)]
+pub struct RoleOptions {
+    pub network_seed: Option<NetworkSeed>,
+    pub properties: Option<YamlProperties>,
+    pub origin_time: Option<Timestamp>,
+    /// Maximum number of clones that can be created for this role
+    pub clone_limit: Option<u32>,
+    /// Don't create a cell for this role when installing the app, it can only be cloned
+    pub disabled: bool,
+}
+
+/// Result of installing an app
+#[derive(Debug, Clone)]
+pub enum AppInstallation {
+    Installed(holochain_client::AppInfo),
+    /// The app was not installed yet because its membrane proofs were deferred
+    AwaitingMembraneProofs {
+        agent_pub_key: AgentPubKey,
+    },
+}
+
+/// An app whose installation is waiting for its membrane proofs
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct AppAwaitingMembraneProofs {
+    /// Hash of the bundle for the app in the hApp bundle store, with the network seed and the role options already applied
+    pub happ_bundle_hash: String,
+    pub agent_pub_key: AgentPubKey,
+    /// Added to the installed apps once the app is installed
+    pub installed_app_info: InstalledAppInfo,
+}
+
+pub(crate) type AppsAwaitingMembraneProofsStore =
+    JsonStore<HashMap<InstalledAppId, AppAwaitingMembraneProofs>>;
+
+/// Applies the network seed and the role options to the manifest of the given bundle
+///
+/// The network seed is applied to every role that doesn't override it in `roles_options`
+pub(crate) fn apply_role_options(
+    app_bundle: AppBundle,
+    network_seed: Option<&NetworkSeed>,
+    roles_options: &HashMap<RoleName, RoleOptions>,
+) -> crate::Result<AppBundle> {
+    if network_seed.is_none() && roles_options.is_empty() {
+        return Ok(app_bundle);
+    }
+
+    let mut manifest = app_bundle.manifest().clone();
+    let roles = match &mut manifest {
+        AppManifest::V1(manifest) => &mut manifest.roles,
+    };
+
+    if let Some(role_name) = roles_options
+        .keys()
+        .find(|role_name| !roles.iter().any(|role| role.name.eq(*role_name)))
+    {
+        return Err(crate::Error::InvalidInstallOptions(format!(
+            "role {role_name} is not in the manifest of the app"
+        )));
+    }
+
+    for role in roles.iter_mut() {
+        let options = roles_options.get(&role.name).cloned().unwrap_or_default();
+
+        let modifiers = &mut role.dna.modifiers;
+        if let Some(network_seed) = options.network_seed.as_ref().or(network_seed) {
+            modifiers.network_seed = Some(network_seed.clone());
+        }
+        if let Some(properties) = options.properties {
+            modifiers.properties = Some(properties);
+        }
+        if let Some(origin_time) = options.origin_time {
+            modifiers.origin_time = Some(origin_time);
+        }
+        if let Some(clone_limit) = options.clone_limit {
+            role.dna.clone_limit = clone_limit;
+        }
+        if options.disabled {
+            role.provisioning = Some(CellProvisioning::CloneOnly);
+        }
+    }
+
+    let bundle = app_bundle.into_inner().update_manifest(manifest)?;
+
+    Ok(bundle.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use holochain::prelude::{AppManifestV1, AppRoleManifest};
+
+    use super::*;
+
+    fn app_bundle() -> AppBundle {
+        let manifest: AppManifest = AppManifestV1 {
+            name: String::from("forum"),
+            description: None,
+            roles: vec![
+                AppRoleManifest::sample(RoleName::from("forum")),
+                AppRoleManifest::sample(RoleName::from("profiles")),
+            ],
+        }
+        .into();
+        mr_bundle::Bundle::new_unchecked(manifest, vec![])
+            .unwrap()
+            .into()
+    }
+
+    fn role<'a>(app_bundle: &'a AppBundle, role_name: &str) -> &'a AppRoleManifest {
+        let AppManifest::V1(manifest) = app_bundle.manifest();
+        manifest
+            .roles
+            .iter()
+            .find(|role| role.name.eq(role_name))
+            .unwrap()
+    }
+
+    #[test]
+    fn network_seed_applies_to_every_role() {
+        let network_seed = NetworkSeed::from("seed");
+
+        let app_bundle =
+            apply_role_options(app_bundle(), Some(&network_seed), &HashMap::new()).unwrap();
+
+        for role_name in ["forum", "profiles"] {
+            assert_eq!(
+                role(&app_bundle, role_name).dna.modifiers.network_seed,
+                Some(network_seed.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn role_network_seed_overrides_the_network_seed() {
+        let network_seed = NetworkSeed::from("seed");
+        let roles_options = HashMap::from([(
+            RoleName::from("profiles"),
+            RoleOptions {
+                network_seed: Some(NetworkSeed::from("profiles-seed")),
+                ..Default::default()
+            },
+        )]);
+
+        let app_bundle =
+            apply_role_options(app_bundle(), Some(&network_seed), &roles_options).unwrap();
+
+        assert_eq!(
+            role(&app_bundle, "forum").dna.modifiers.network_seed,
+            Some(network_seed)
+        );
+        assert_eq!(
+            role(&app_bundle, "profiles").dna.modifiers.network_seed,
+            Some(NetworkSeed::from("profiles-seed"))
+        );
+    }
+
+    #[test]
+    fn clone_limit_and_disabled_apply_to_their_role() {
+        let roles_options = HashMap::from([(
+            RoleName::from("profiles"),
+            RoleOptions {
+                clone_limit: Some(3),
+                disabled: true,
+                ..Default::default()
+            },
+        )]);
+
+        let app_bundle = apply_role_options(app_bundle(), None, &roles_options).unwrap();
+
+        let profiles = role(&app_bundle, "profiles");
+        assert_eq!(profiles.dna.clone_limit, 3);
+        assert_eq!(profiles.provisioning, Some(CellProvisioning::CloneOnly));
+
+        let forum = role(&app_bundle, "forum");
+        assert_eq!(forum.dna.clone_limit, 0);
+        assert_ne!(forum.provisioning, Some(CellProvisioning::CloneOnly));
+        assert_eq!(forum.dna.modifiers.network_seed, None);
+    }
+
+    #[test]
+    fn unknown_roles_are_rejected() {
+        let roles_options = HashMap::from([(RoleName::from("chat"), RoleOptions::default())]);
+
+        assert!(matches!(
+            apply_role_options(app_bundle(), None, &roles_options),
+            Err(crate::Error::InvalidInstallOptions(_))
+        ));
+    }
+}
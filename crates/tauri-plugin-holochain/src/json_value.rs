@@ -0,0 +1,152 @@
+use std::fmt;
+
+use serde::{
+    de::{MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use serde_json::{Map, Number, Value};
+
+/// JSON representation of a msgpack-encoded value
+///
+/// `serde_json::Value` can't be deserialized from byte arrays, which are very common in zome call results and signals
+#[derive(Debug)]
+pub struct JsonValue(pub Value);
+
+impl<'de> Deserialize<'de> for JsonValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_any(JsonValueVisitor)
+            .map(JsonValue)
+    }
+}
+
+struct JsonValueVisitor;
+
+impl<'de> Visitor<'de> for JsonValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a msgpack value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Number::from_f64(v).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::Array(
+            v.iter().map(|b| Value::Number((*b).into())).collect(),
+        ))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Value, E> {
+        self.visit_bytes(&v)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut values = Vec::new();
+        while let Some(JsonValue(value)) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut values = Map::new();
+        while let Some((JsonValue(key), JsonValue(value))) = map.next_entry()? {
+            let key = match key {
+                Value::String(key) => key,
+                key => key.to_string(),
+            };
+            values.insert(key, value);
+        }
+        Ok(Value::Object(values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use holochain::prelude::{ActionHash, ExternIO};
+    use serde::Serialize;
+
+    #[derive(Serialize, Debug)]
+    struct Post {
+        title: String,
+        tags: Vec<String>,
+        author: Option<ActionHash>,
+        likes: u32,
+    }
+
+    #[test]
+    fn decodes_zome_call_results_with_bytes_into_json() {
+        let hash = ActionHash::from_raw_36(vec![1; 36]);
+        let post = Post {
+            title: String::from("hi"),
+            tags: vec![String::from("a")],
+            author: Some(hash.clone()),
+            likes: 3,
+        };
+
+        let JsonValue(value) = ExternIO::encode(post).unwrap().decode().unwrap();
+
+        let hash_bytes: Vec<Value> = hash
+            .get_raw_39()
+            .iter()
+            .map(|b| Value::Number((*b).into()))
+            .collect();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "title": "hi",
+                "tags": ["a"],
+                "author": hash_bytes,
+                "likes": 3
+            })
+        );
+    }
+}
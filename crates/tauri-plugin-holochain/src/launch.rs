@@ -1,12 +1,19 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
 
 use async_std::sync::Mutex;
 
 use hc_seed_bundle::dependencies::sodoken::BufRead;
-use holochain::conductor::Conductor;
+use holochain::conductor::{error::ConductorError, Conductor, ConductorHandle};
 use holochain_client::AdminWebsocket;
 
-use crate::{filesystem::FileSystem, HolochainPluginConfig, HolochainRuntime};
+use crate::{
+    app_interfaces::AppInterfaces, filesystem::FileSystem, health::HolochainHealth,
+    signals::SignalSubscriptions, AdminPort, HolochainPluginConfig, HolochainRuntime,
+};
 
 fn override_gossip_arc_clamping() -> Option<String> {
     if cfg!(mobile) {
@@ -21,7 +28,7 @@ fn override_gossip_arc_clamping() -> Option<String> {
 /// Launch the holochain conductor in the background
 pub async fn launch_holochain_runtime(
     passphrase: BufRead,
-    config: HolochainPluginConfig,
+    config: &HolochainPluginConfig,
 ) -> crate::Result<HolochainRuntime> {
     // let mut lock = RUNNING_HOLOCHAIN.write().await;
 
@@ -29,55 +36,164 @@ pub async fn launch_holochain_runtime(
     //     return Ok(info);
     // }
 
-    let filesystem = FileSystem::new(config.holochain_dir).await?;
-    let admin_port = portpicker::pick_unused_port().expect("No ports free");
-
-    let config = crate::config::conductor_config(
-        &filesystem,
-        admin_port,
-        filesystem.keystore_dir().into(),
-        config.bootstrap_url,
-        config.signal_url,
-        override_gossip_arc_clamping(),
-    );
-
-    let conductor_handle = Conductor::builder()
-        .config(config)
-        .passphrase(Some(passphrase))
-        .build()
-        .await?;
-
-    wait_until_admin_ws_is_available(admin_port).await?;
-    log::info!("Connected to the admin websocket");
+    let filesystem = FileSystem::new(config.holochain_dir.clone()).await?;
+    let (conductor_handle, admin_port) =
+        start_conductor(&filesystem, &config.admin_port, passphrase.clone(), config).await?;
+    log::info!("Connected to the admin websocket at port {admin_port}");
 
     // *lock = Some(info.clone());
 
     Ok(HolochainRuntime {
         filesystem,
         apps_websockets_auths: Arc::new(Mutex::new(HashMap::new())),
+        issued_app_websocket_auths: Arc::new(Mutex::new(HashMap::new())),
+        app_interfaces: Arc::new(Mutex::new(AppInterfaces::default())),
+        apps_windows: Arc::new(Mutex::new(HashMap::new())),
+        apps_websockets: Arc::new(Mutex::new(HashMap::new())),
+        signal_subscriptions: Arc::new(Mutex::new(SignalSubscriptions::default())),
+        apps_forwarding_signals: Arc::new(Mutex::new(HashMap::new())),
+        apps_notifications: Arc::new(Mutex::new(HashMap::new())),
+        pending_membrane_proof_requests: Arc::new(Mutex::new(HashMap::new())),
+        health: Arc::new(Mutex::new(HolochainHealth::default())),
         admin_port,
-        conductor_handle,
+        passphrase,
+        conductor_handle: Arc::new(RwLock::new(Some(conductor_handle))),
+        releasing_conductor: Arc::new(Mutex::new(None)),
     })
 }
 
-pub async fn wait_until_admin_ws_is_available(admin_port: u16) -> crate::Result<()> {
-    let mut retry_count = 0;
-    loop {
-        if let Err(err) = AdminWebsocket::connect(format!("localhost:{}", admin_port)).await {
-            log::error!("Could not connect to the admin interface: {}", err);
-        } else {
-            break;
+/// Builds and starts the conductor, and adds its admin interface on the first of the given ports that can be bound,
+/// failing with `Error::StartupTimeout` if it's not ready within `HolochainPluginConfig::startup_timeout`
+///
+/// The conductor is built only once, without any admin interface, which is then added on each of the ports in turn.
+/// If no admin interface can be added, the conductor is shut down before returning the error,
+/// and if it's still being built when the timeout elapses, it's shut down in the background once it's built.
+///
+/// Returns the conductor along with the port its admin interface was bound to
+pub(crate) async fn start_conductor(
+    filesystem: &FileSystem,
+    admin_port: &AdminPort,
+    passphrase: BufRead,
+    config: &HolochainPluginConfig,
+) -> crate::Result<(ConductorHandle, u16)> {
+    let ports_to_bind = admin_port.ports_to_bind()?;
+    let startup_timeout = config.startup_timeout;
+    let started_at = Instant::now();
+
+    let conductor_config = crate::config::conductor_config(
+        filesystem,
+        filesystem.keystore_dir().into(),
+        config.bootstrap_url.clone(),
+        config.signal_url.clone(),
+        override_gossip_arc_clamping(),
+    );
+
+    // Built in its own task, so that a conductor that is still being built when the timeout elapses
+    // is shut down once it's built, instead of being dropped halfway through
+    let mut build = tauri::async_runtime::spawn(
+        Conductor::builder()
+            .config(conductor_config)
+            .passphrase(Some(passphrase))
+            .build(),
+    );
+    let conductor_handle = match async_std::future::timeout(startup_timeout, &mut build).await {
+        Ok(built) => built??,
+        Err(_) => {
+            tauri::async_runtime::spawn(async move {
+                if let Ok(Ok(conductor_handle)) = build.await {
+                    shut_down(conductor_handle).await;
+                }
+            });
+            return Err(crate::Error::StartupTimeout {
+                timeout: startup_timeout,
+                stage: String::from("building the conductor"),
+            });
+        }
+    };
+
+    let admin_port = match add_admin_interface(&conductor_handle, ports_to_bind).await {
+        Ok(admin_port) => admin_port,
+        Err(err) => {
+            shut_down(conductor_handle).await;
+            return Err(err);
         }
-        async_std::task::sleep(Duration::from_millis(200)).await;
+    };
+
+    let remaining = startup_timeout.saturating_sub(started_at.elapsed());
+    if let Err(err) = connect_to_admin_interface(admin_port, startup_timeout, remaining).await {
+        shut_down(conductor_handle).await;
+        return Err(err);
+    }
+
+    Ok((conductor_handle, admin_port))
+}
 
-        retry_count += 1;
-        if retry_count == 200 {
-            return Err(crate::Error::AdminWebsocketError(
-                "Can't connect to holochain".to_string(),
-            ));
+/// Adds the admin interface of the conductor on the first of the given ports that can be bound,
+/// moving on to the next one when binding fails
+///
+/// Returns the port the admin interface was bound to
+async fn add_admin_interface(
+    conductor_handle: &ConductorHandle,
+    ports_to_bind: Vec<u16>,
+) -> crate::Result<u16> {
+    let last_port = ports_to_bind.last().cloned();
+
+    for port in ports_to_bind {
+        match conductor_handle
+            .clone()
+            .add_admin_interfaces(vec![crate::config::admin_interface_config(port)])
+            .await
+        {
+            Ok(bound_ports) => {
+                return bound_ports
+                    .first()
+                    .cloned()
+                    .ok_or(crate::Error::AdminWebsocketError(String::from(
+                        "the conductor did not bind the admin interface",
+                    )))
+            }
+            Err(ConductorError::InterfaceError(err)) if Some(port) != last_port => {
+                log::warn!("Could not bind the admin interface to port {port}, trying the next one: {err:?}");
+            }
+            Err(err) => return Err(err.into()),
         }
     }
-    Ok(())
+
+    Err(crate::Error::AdminPortUnavailable(String::from(
+        "no port to bind",
+    )))
+}
+
+/// Shuts down a conductor that failed to start, so that it releases the keystore and the databases
+async fn shut_down(conductor_handle: ConductorHandle) {
+    match conductor_handle.shutdown().await {
+        Ok(Ok(())) => log::info!("Shut down the conductor that failed to start"),
+        result => log::error!("Failed to shut down the conductor that failed to start: {result:?}"),
+    }
+}
+
+/// Connects once to the admin interface of the conductor to check that it accepts connections
+///
+/// The admin interface is bound by the time it's added, so there is nothing to wait for:
+/// failing to connect is an `Error::AdminInterfaceConnectionError`, and only running out of `remaining` time
+/// is an `Error::StartupTimeout`
+async fn connect_to_admin_interface(
+    admin_port: u16,
+    startup_timeout: Duration,
+    remaining: Duration,
+) -> crate::Result<()> {
+    let connect = AdminWebsocket::connect(format!("localhost:{}", admin_port));
+    match async_std::future::timeout(remaining, connect).await {
+        Ok(Ok(_admin_ws)) => Ok(()),
+        Ok(Err(err)) => Err(crate::Error::AdminInterfaceConnectionError {
+            port: admin_port,
+            source: err.into(),
+        }),
+        Err(_) => Err(crate::Error::StartupTimeout {
+            timeout: startup_timeout,
+            stage: String::from("connecting to the admin interface"),
+        }),
+    }
 }
 
 // pub async fn wait_until_app_ws_is_available(app_port: u16) -> crate::Result<()> {
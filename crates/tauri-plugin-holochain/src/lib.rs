@@ -1,62 +1,220 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     path::PathBuf,
-    sync::Arc,
+    sync::{Arc, PoisonError, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use async_std::sync::Mutex;
 use hc_seed_bundle::dependencies::sodoken::BufRead;
-use http_server::{pong_iframe, read_asset};
+use http_server::{parse_happ_uri, pong_iframe, read_asset};
 use launch::launch_holochain_runtime;
+use serde::Serialize;
 use tauri::{
     http::response,
     ipc::CapabilityBuilder,
     plugin::{Builder, TauriPlugin},
-    AppHandle, Manager, Runtime, WebviewUrl, WebviewWindowBuilder,
+    AppHandle, Manager, RunEvent, Runtime, WebviewUrl, WebviewWindowBuilder, WindowEvent,
 };
 
-use holochain::{
-    conductor::ConductorHandle,
-    prelude::{AppBundle, MembraneProof, NetworkSeed, RoleName},
+use holochain::{conductor::ConductorHandle, prelude::AppBundle};
+use holochain_client::{
+    AdminWebsocket, AppInfo, AppWebsocket, ConductorApiError, InstalledAppId, LairAgentSigner,
 };
-use holochain_client::{AdminWebsocket, AppInfo, AppWebsocket, InstalledAppId, LairAgentSigner};
-use holochain_types::{web_app::WebAppBundle, websocket::AllowedOrigins};
+use holochain_types::web_app::WebAppBundle;
 
+mod agents;
+mod app_interfaces;
+mod bundled_apps;
+mod clone_cells;
 mod commands;
 mod config;
+mod csp;
 mod error;
 mod filesystem;
+mod health;
 mod http_server;
+mod install_options;
+mod json_value;
 mod launch;
-
-use commands::install_web_app::{install_app, install_web_app, update_app, UpdateAppError};
+mod membrane_proofs;
+mod migration;
+mod notifications;
+mod origin;
+mod publisher;
+mod recovery;
+mod signals;
+mod update_channel;
+mod zome_call_signer;
+
+pub use agents::{AgentKeySource, LocalAgent};
+pub use app_interfaces::AppInterfaceStrategy;
+use app_interfaces::AppInterfaces;
+pub use bundled_apps::{
+    BundledApp, BundledAppBytes, BundledAppFailedPayload, BUNDLED_APP_FAILED_EVENT,
+};
+pub use clone_cells::{
+    CloneCellIdPayload, ClonedCellPayload, CLONE_CELL_CREATED_EVENT, CLONE_CELL_DELETED_EVENT,
+    CLONE_CELL_DISABLED_EVENT, CLONE_CELL_ENABLED_EVENT,
+};
+use commands::install_web_app::{install_app, plan_update, update_app};
+pub use commands::install_web_app::{
+    CellUpdateOutcome, CellUpdatePlan, CellUpdateResult, CoordinatorZomeChange,
+    CoordinatorZomePlan, UpdateAppError, UpdatePlan,
+};
+pub use config::AdminPort;
+pub use csp::ContentSecurityPolicyConfig;
 pub use error::{Error, Result};
-use filesystem::{AppBundleStore, BundleStore, FileSystem};
+use filesystem::{AppBundleStore, BundleStore, FileSystem, InstalledAppInfo, UiStore};
+pub use health::{
+    HealthMonitorConfig, HealthStatus, HolochainHealth, APP_WEBSOCKET_AUTH_REISSUED_EVENT,
+    HOLOCHAIN_HEALTH_EVENT,
+};
+use install_options::apply_role_options;
+pub use install_options::{AppInstallation, InstallOptions, RoleOptions};
+use membrane_proofs::{collect_membrane_proofs, PendingMembraneProofRequests};
+pub use membrane_proofs::{
+    MembraneProofProvider, MembraneProofRequest, MembraneProofRequestPayload,
+    UiMembraneProofProvider, MEMBRANE_PROOF_REQUESTED_EVENT,
+};
+pub use migration::{AppMigratedPayload, AppMigration, AppUpdate, APP_MIGRATED_EVENT};
+pub use notifications::{AppNotificationsSettings, HappNotification, NotificationUrgency};
+use origin::happ_origin;
+pub use publisher::{PublisherKey, PublisherSignatureError};
+pub use recovery::MASTER_SEED_TAG;
+use signals::SignalSubscriptions;
+pub use signals::{AppSignalPayload, SignalSubscription, SIGNAL_EVENT};
+pub use update_channel::{ReleaseManifest, UpdateChannelConfig, UpdateCheck};
 use url2::Url2;
-
-const ZOME_CALL_SIGNER_INITIALIZATION_SCRIPT: &'static str = include_str!("../zome-call-signer.js");
+pub use zome_call_signer::ZomeCallSignerConfig;
 
 /// Access to the holochain APIs.
 pub struct HolochainPlugin<R: Runtime> {
     pub app_handle: AppHandle<R>,
     pub holochain_runtime: HolochainRuntime,
+    pub(crate) config: HolochainPluginConfig,
 }
 
-#[derive(Clone)]
+/// Tokens with less than this time left are not handed out anymore, to give clients time to connect with them
+const APP_AUTH_TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Serialize)]
 pub struct AppWebsocketAuth {
     pub app_websocket_port: u16,
     pub token: Vec<u8>,
+    /// Serialized as milliseconds since the UNIX epoch, so that clients can refresh the token before it expires
+    #[serde(serialize_with = "serialize_as_millis_since_epoch")]
+    pub expires_at: SystemTime,
+}
+
+fn serialize_as_millis_since_epoch<S: serde::Serializer>(
+    time: &SystemTime,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_u64(millis_since_epoch(time))
+}
+
+fn millis_since_epoch(time: &SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+impl AppWebsocketAuth {
+    /// When the token expires, in milliseconds since the UNIX epoch
+    pub fn expires_at_millis(&self) -> u64 {
+        millis_since_epoch(&self.expires_at)
+    }
+
+    fn is_about_to_expire(&self) -> bool {
+        SystemTime::now() + APP_AUTH_TOKEN_EXPIRY_MARGIN >= self.expires_at
+    }
+
+    fn is_expired(&self) -> bool {
+        SystemTime::now() >= self.expires_at
+    }
+}
+
+/// Drops the tokens that have expired, since the conductor doesn't accept them anymore
+fn without_expired(app_websocket_auths: Vec<AppWebsocketAuth>) -> Vec<AppWebsocketAuth> {
+    app_websocket_auths
+        .into_iter()
+        .filter(|app_websocket_auth| !app_websocket_auth.is_expired())
+        .collect()
+}
+
+/// Whether the error means that the connection of an `AppWebsocket` is broken, rather than the request failing in the conductor
+fn is_connection_error(err: &ConductorApiError) -> bool {
+    matches!(
+        err,
+        ConductorApiError::WebsocketError(_) | ConductorApiError::IoError(_)
+    )
+}
+
+/// The commands that the windows of an app can call
+const APP_WINDOW_PERMISSIONS: [&str; 7] = [
+    "holochain:allow-sign-zome-call",
+    "holochain:allow-get-app-auth-token",
+    "holochain:allow-call-zome",
+    "holochain:allow-create-clone-cell",
+    "holochain:allow-enable-clone-cell",
+    "holochain:allow-disable-clone-cell",
+    "holochain:allow-delete-clone-cell",
+];
+
+/// Allows the window with the given label to call the commands in `APP_WINDOW_PERMISSIONS`
+fn app_window_capability(label: String) -> CapabilityBuilder {
+    let mut capability_builder = CapabilityBuilder::new("sign-zome-call");
+    for permission in APP_WINDOW_PERMISSIONS {
+        capability_builder = capability_builder.permission(permission);
+    }
+
+    #[cfg(desktop)] // TODO: remove this check
+    {
+        capability_builder = capability_builder.window(label);
+    }
+    #[cfg(mobile)] // TODO: remove this check
+    {
+        let _ = label;
+        capability_builder = capability_builder.windows(["*"]);
+    }
+
+    capability_builder
 }
 
 pub struct HolochainRuntime {
     pub filesystem: FileSystem,
     pub apps_websockets_auths: Arc<Mutex<HashMap<String, AppWebsocketAuth>>>,
+    /// All the auth tokens issued for each app that have not expired yet, revoked when its last window is closed
+    pub(crate) issued_app_websocket_auths:
+        Arc<Mutex<HashMap<InstalledAppId, Vec<AppWebsocketAuth>>>>,
+    pub(crate) app_interfaces: Arc<Mutex<AppInterfaces>>,
+    pub(crate) apps_windows: Arc<Mutex<HashMap<String, InstalledAppId>>>,
+    pub(crate) apps_websockets: Arc<Mutex<HashMap<InstalledAppId, AppWebsocket>>>,
+    pub(crate) signal_subscriptions: Arc<Mutex<SignalSubscriptions>>,
+    pub(crate) apps_forwarding_signals: Arc<Mutex<HashMap<InstalledAppId, SignalSubscription>>>,
+    pub(crate) apps_notifications: Arc<Mutex<HashMap<InstalledAppId, SignalSubscription>>>,
+    pub(crate) pending_membrane_proof_requests: PendingMembraneProofRequests,
+    pub(crate) health: Arc<Mutex<HolochainHealth>>,
+    /// The port the admin interface of the conductor is bound to
     pub admin_port: u16,
-    pub(crate) conductor_handle: ConductorHandle,
+    /// Kept to build the conductor again if it needs to be restarted
+    pub(crate) passphrase: BufRead,
+    /// Replaced with a new conductor when it's restarted, `None` between shutting down the old one and building the new one
+    pub(crate) conductor_handle: Arc<RwLock<Option<ConductorHandle>>>,
+    /// The conductor that was shut down by a restart but is still referenced, kept until it's released
+    pub(crate) releasing_conductor: Arc<Mutex<Option<ConductorHandle>>>,
 }
 
-fn happ_origin(app_id: &String) -> Url2 {
-    url2::url2!("happ://{app_id}")
+impl HolochainRuntime {
+    /// The conductor that is currently running
+    pub(crate) fn conductor_handle(&self) -> crate::Result<ConductorHandle> {
+        self.conductor_handle
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+            .ok_or(crate::Error::ConductorNotRunning)
+    }
 }
 
 impl<R: Runtime> HolochainPlugin<R> {
@@ -79,13 +237,18 @@ impl<R: Runtime> HolochainPlugin<R> {
             .map(|n| n.to_string())
             .collect();
         let token = token_vector.join(",");
-        let url_origin = happ_origin(&app_id.clone().into());
+        let url_origin = happ_origin(&app_id);
 
         let url_path = url_path.unwrap_or_default();
 
         let webview_url = tauri::WebviewUrl::CustomProtocol(url::Url::parse(
             format!("{url_origin}/{url_path}").as_str(),
         )?);
+        tauri::async_runtime::block_on(async {
+            self.register_app_window(app_id.clone(), app_id.clone())
+                .await
+        });
+
         let window_builder =
             WebviewWindowBuilder::new(&self.app_handle, app_id.clone(), webview_url)
                 .initialization_script(
@@ -94,27 +257,25 @@ impl<R: Runtime> HolochainPlugin<R> {
             if (!window.__HC_LAUNCHER_ENV__) window.__HC_LAUNCHER_ENV__ = {{}};
             window.__HC_LAUNCHER_ENV__.APP_INTERFACE_PORT = {};
             window.__HC_LAUNCHER_ENV__.APP_INTERFACE_TOKEN = [{}];
+            window.__HC_LAUNCHER_ENV__.APP_INTERFACE_TOKEN_EXPIRES_AT = {};
             window.__HC_LAUNCHER_ENV__.INSTALLED_APP_ID = "{}";
         "#,
-                        app_websocket_auth.app_websocket_port, token, app_id
+                        app_websocket_auth.app_websocket_port,
+                        token,
+                        app_websocket_auth.expires_at_millis(),
+                        app_id
                     )
                     .as_str(),
                 )
-                .initialization_script(ZOME_CALL_SIGNER_INITIALIZATION_SCRIPT);
-
-        let mut capability_builder =
-            CapabilityBuilder::new("sign-zome-call").permission("holochain:allow-sign-zome-call");
-
-        #[cfg(desktop)] // TODO: remove this check
-        {
-            capability_builder = capability_builder.window(app_id);
-        }
-        #[cfg(mobile)] // TODO: remove this check
-        {
-            capability_builder = capability_builder.windows(["*"]);
-        }
+                .initialization_script(
+                    self.config
+                        .zome_call_signer
+                        .initialization_script()?
+                        .as_str(),
+                );
 
-        self.app_handle.add_capability(capability_builder)?;
+        self.app_handle
+            .add_capability(app_window_capability(app_id))?;
 
         Ok(window_builder)
     }
@@ -153,11 +314,28 @@ impl<R: Runtime> HolochainPlugin<R> {
                     self.holochain_runtime.admin_port
                 )
                 .as_str(),
-            )
+            );
+
+            // Admin windows install apps, so they need to be able to answer the membrane proof requests
+            let mut capability_builder = CapabilityBuilder::new("provide-membrane-proof")
+                .permission("holochain:allow-provide-membrane-proof");
+
+            #[cfg(desktop)] // TODO: remove this check
+            {
+                capability_builder = capability_builder.window(label.clone());
+            }
+            #[cfg(mobile)] // TODO: remove this check
+            {
+                capability_builder = capability_builder.windows(["*"]);
+            }
+
+            self.app_handle.add_capability(capability_builder)?;
         }
 
         if let Some(enabled_app) = enabled_app {
             let app_websocket_auth = tauri::async_runtime::block_on(async {
+                self.register_app_window(label.clone(), enabled_app.clone())
+                    .await;
                 self.get_app_websocket_auth(&enabled_app).await
             })?;
 
@@ -174,27 +352,25 @@ impl<R: Runtime> HolochainPlugin<R> {
             if (!window.__HC_LAUNCHER_ENV__) window.__HC_LAUNCHER_ENV__ = {{}};
             window.__HC_LAUNCHER_ENV__.APP_INTERFACE_PORT = {};
             window.__HC_LAUNCHER_ENV__.APP_INTERFACE_TOKEN = [{}];
+            window.__HC_LAUNCHER_ENV__.APP_INTERFACE_TOKEN_EXPIRES_AT = {};
             window.__HC_LAUNCHER_ENV__.INSTALLED_APP_ID = "{}";
         "#,
-                        app_websocket_auth.app_websocket_port, token, enabled_app
+                        app_websocket_auth.app_websocket_port,
+                        token,
+                        app_websocket_auth.expires_at_millis(),
+                        enabled_app
                     )
                     .as_str(),
                 )
-                .initialization_script(ZOME_CALL_SIGNER_INITIALIZATION_SCRIPT);
-
-            let mut capability_builder = CapabilityBuilder::new("sign-zome-call")
-                .permission("holochain:allow-sign-zome-call");
-
-            #[cfg(desktop)] // TODO: remove this check
-            {
-                capability_builder = capability_builder.window(label);
-            }
-            #[cfg(mobile)] // TODO: remove this check
-            {
-                capability_builder = capability_builder.windows(["*"]);
-            }
-
-            self.app_handle.add_capability(capability_builder)?;
+                .initialization_script(
+                    self.config
+                        .zome_call_signer
+                        .initialization_script()?
+                        .as_str(),
+                );
+
+            self.app_handle
+                .add_capability(app_window_capability(label))?;
         }
 
         Ok(window_builder)
@@ -209,53 +385,216 @@ impl<R: Runtime> HolochainPlugin<R> {
         Ok(admin_ws)
     }
 
-    async fn get_app_websocket_auth(
+    /// Returns the port and a valid auth token for the app websocket of the given app
+    ///
+    /// Tokens are cached until they are about to expire, at which point a new one is issued
+    pub(crate) async fn get_app_websocket_auth(
+        &self,
+        app_id: &InstalledAppId,
+    ) -> crate::Result<AppWebsocketAuth> {
+        let cached_app_websocket_auth = self
+            .holochain_runtime
+            .apps_websockets_auths
+            .lock()
+            .await
+            .get(app_id)
+            .cloned();
+        if let Some(app_websocket_auth) = cached_app_websocket_auth {
+            if !app_websocket_auth.is_about_to_expire() {
+                return Ok(app_websocket_auth);
+            }
+        }
+
+        let app_websocket_auth = self.issue_app_websocket_auth(app_id).await?;
+
+        // The replaced token is kept valid, since other windows of the app may still be using it
+        self.holochain_runtime
+            .apps_websockets_auths
+            .lock()
+            .await
+            .insert(app_id.clone(), app_websocket_auth.clone());
+        Ok(app_websocket_auth)
+    }
+
+    /// Issues a new auth token for the app websocket of the given app, replacing the cached one
+    ///
+    /// The replaced token is not revoked, since other windows of the app may still be using it,
+    /// so it stays valid until it expires or the last window of the app is closed
+    ///
+    /// * `app_id` - the app to issue the token for
+    pub async fn refresh_app_websocket_auth(
         &self,
         app_id: &InstalledAppId,
     ) -> crate::Result<AppWebsocketAuth> {
-        let mut apps_websockets_auths = self.holochain_runtime.apps_websockets_auths.lock().await;
-        if let Some(app_websocket_auth) = apps_websockets_auths.get(app_id) {
-            return Ok(app_websocket_auth.clone());
+        let app_websocket_auth = self.issue_app_websocket_auth(app_id).await?;
+
+        self.holochain_runtime
+            .apps_websockets_auths
+            .lock()
+            .await
+            .insert(app_id.clone(), app_websocket_auth.clone());
+        Ok(app_websocket_auth)
+    }
+
+    /// Revokes all the auth tokens issued for the app websocket of the given app that have not expired yet
+    ///
+    /// Connections that were already established with the tokens are not affected
+    ///
+    /// * `app_id` - the app whose tokens should be revoked
+    pub async fn revoke_app_websocket_auth(&self, app_id: &InstalledAppId) -> crate::Result<()> {
+        self.holochain_runtime
+            .apps_websockets_auths
+            .lock()
+            .await
+            .remove(app_id);
+        let issued_app_websocket_auths = self
+            .holochain_runtime
+            .issued_app_websocket_auths
+            .lock()
+            .await
+            .remove(app_id)
+            .unwrap_or_default();
+        let unexpired_app_websocket_auths = without_expired(issued_app_websocket_auths);
+        if unexpired_app_websocket_auths.is_empty() {
+            return Ok(());
         }
 
         let admin_ws = self.admin_websocket().await?;
+        for app_websocket_auth in unexpired_app_websocket_auths.iter() {
+            admin_ws
+                .revoke_app_authentication_token(app_websocket_auth.token.clone())
+                .await
+                .map_err(|err| crate::Error::ConductorApiError(err))?;
+        }
 
-        // Allow any when the app is build in debug mode to allow normal tauri development pointing to http://localhost:1420
-        let allowed_origins = if tauri::is_dev() {
-            AllowedOrigins::Any
-        } else {
-            let mut origins: HashSet<String> = HashSet::new();
-            origins.insert(happ_origin(app_id).to_string());
-            AllowedOrigins::Origins(origins)
-        };
+        log::info!(
+            "Revoked {} app auth tokens for app {app_id}",
+            unexpired_app_websocket_auths.len()
+        );
+
+        Ok(())
+    }
 
-        let app_port = admin_ws
-            .attach_app_interface(0, allowed_origins, Some(app_id.clone()))
+    async fn issue_app_websocket_auth(
+        &self,
+        app_id: &InstalledAppId,
+    ) -> crate::Result<AppWebsocketAuth> {
+        let admin_ws = self.admin_websocket().await?;
+        let app_websocket_port = self.get_app_websocket_port(&admin_ws, app_id).await?;
+
+        self.issue_app_auth_token(&admin_ws, app_id, app_websocket_port)
             .await
-            .map_err(|err| crate::Error::ConductorApiError(err))?;
+    }
 
+    /// Issues a new auth token for the given app, to connect to the app interface at the given port
+    pub(crate) async fn issue_app_auth_token(
+        &self,
+        admin_ws: &AdminWebsocket,
+        app_id: &InstalledAppId,
+        app_websocket_port: u16,
+    ) -> crate::Result<AppWebsocketAuth> {
+        let expiry = self.config.app_auth_token_expiry;
         let response = admin_ws
             .issue_app_auth_token(
                 holochain_conductor_api::IssueAppAuthenticationTokenPayload {
                     installed_app_id: app_id.clone(),
-                    expiry_seconds: 999999999,
+                    expiry_seconds: expiry.as_secs(),
                     single_use: false,
                 },
             )
             .await
             .map_err(|err| crate::Error::ConductorApiError(err))?;
 
-        let token = response.token;
-
         let app_websocket_auth = AppWebsocketAuth {
-            app_websocket_port: app_port,
-            token,
+            app_websocket_port,
+            token: response.token,
+            expires_at: SystemTime::now() + expiry,
         };
 
-        apps_websockets_auths.insert(app_id.clone(), app_websocket_auth.clone());
+        let mut issued_app_websocket_auths = self
+            .holochain_runtime
+            .issued_app_websocket_auths
+            .lock()
+            .await;
+        let app_websocket_auths = issued_app_websocket_auths
+            .remove(app_id)
+            .unwrap_or_default();
+        let mut app_websocket_auths = without_expired(app_websocket_auths);
+        app_websocket_auths.push(app_websocket_auth.clone());
+        issued_app_websocket_auths.insert(app_id.clone(), app_websocket_auths);
+
         Ok(app_websocket_auth)
     }
 
+    /// Records that the window with the given label belongs to the given app,
+    /// and starts forwarding the signals of the app to its windows
+    async fn register_app_window(&self, label: String, app_id: InstalledAppId) {
+        self.holochain_runtime
+            .apps_windows
+            .lock()
+            .await
+            .insert(label, app_id.clone());
+
+        if let Err(err) = self.forward_signals_to_windows(&app_id).await {
+            log::error!("Failed to forward signals for app {app_id}: {err:?}");
+        }
+    }
+
+    /// Returns the labels of the windows that belong to the given app
+    pub(crate) async fn get_windows_for_app(&self, app_id: &InstalledAppId) -> Vec<String> {
+        self.holochain_runtime
+            .apps_windows
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, id)| *id == app_id)
+            .map(|(label, _)| label.clone())
+            .collect()
+    }
+
+    /// Returns the app to which the window with the given label belongs, if any
+    ///
+    /// * `label` - the label of the window
+    pub async fn get_app_id_for_window(&self, label: &str) -> Option<InstalledAppId> {
+        self.holochain_runtime
+            .apps_windows
+            .lock()
+            .await
+            .get(label)
+            .cloned()
+    }
+
+    /// Forgets the window with the given label, revoking all the auth tokens issued for its app
+    /// and no longer forwarding its signals if it was the last open window for the app
+    ///
+    /// Also detaches the replaced app interfaces that this window was the last one that may be using
+    async fn on_app_window_destroyed(&self, label: &str) -> crate::Result<()> {
+        let mut apps_windows = self.holochain_runtime.apps_windows.lock().await;
+        let Some(app_id) = apps_windows.remove(label) else {
+            return Ok(());
+        };
+        let app_has_other_windows = apps_windows.values().any(|id| id.eq(&app_id));
+        drop(apps_windows);
+
+        let retired = self
+            .holochain_runtime
+            .app_interfaces
+            .lock()
+            .await
+            .on_window_closed(label);
+        if !retired.is_empty() {
+            let admin_ws = self.admin_websocket().await?;
+            self.detach_retired_interfaces(&admin_ws, retired).await?;
+        }
+
+        if !app_has_other_windows {
+            self.stop_forwarding_signals_to_windows(&app_id).await;
+            self.revoke_app_websocket_auth(&app_id).await?;
+        }
+
+        Ok(())
+    }
+
     /// Builds an `AppWebsocket` for the given app ready to use
     ///
     /// * `app_id` - the app to build the `AppWebsocket` for
@@ -264,13 +603,7 @@ impl<R: Runtime> HolochainPlugin<R> {
         let app_ws = AppWebsocket::connect(
             format!("localhost:{}", app_websocket_auth.app_websocket_port),
             app_websocket_auth.token,
-            Arc::new(LairAgentSigner::new(Arc::new(
-                self.holochain_runtime
-                    .conductor_handle
-                    .keystore()
-                    .lair_client()
-                    .clone(),
-            ))),
+            Arc::new(LairAgentSigner::new(Arc::new(self.lair_client()?))),
         )
         .await
         .map_err(|err| crate::Error::WebsocketConnectionError(format!("{err:?}")))?;
@@ -278,122 +611,452 @@ impl<R: Runtime> HolochainPlugin<R> {
         Ok(app_ws)
     }
 
+    /// Returns an `AppWebsocket` for the given app that is kept open and shared among callers
+    pub(crate) async fn cached_app_websocket(
+        &self,
+        app_id: &InstalledAppId,
+    ) -> crate::Result<AppWebsocket> {
+        let mut apps_websockets = self.holochain_runtime.apps_websockets.lock().await;
+        if let Some(app_ws) = apps_websockets.get(app_id) {
+            return Ok(app_ws.clone());
+        }
+
+        let app_ws = self.app_websocket(app_id.clone()).await?;
+        apps_websockets.insert(app_id.clone(), app_ws.clone());
+        Ok(app_ws)
+    }
+
+    /// Forgets the cached `AppWebsocket` for the given app, so that the next call reconnects
+    pub(crate) async fn drop_cached_app_websocket(&self, app_id: &InstalledAppId) {
+        self.holochain_runtime
+            .apps_websockets
+            .lock()
+            .await
+            .remove(app_id);
+    }
+
+    /// Maps the error of a request made with the cached `AppWebsocket` of the given app,
+    /// dropping it only if the connection failed, so that the next request reconnects
+    ///
+    /// Errors returned by the conductor (e.g. a zome call that fails) leave the connection usable
+    pub(crate) async fn handle_app_websocket_error(
+        &self,
+        app_id: &InstalledAppId,
+        err: ConductorApiError,
+    ) -> crate::Error {
+        if is_connection_error(&err) {
+            self.drop_cached_app_websocket(app_id).await;
+        }
+        crate::Error::ConductorApiError(err)
+    }
+
     /// Install the given `WebAppBundle` in the holochain runtime
     /// It installs the hApp in the holochain conductor, and extracts the UI for it to be opened using `Self::web_happ_window_builder()`
     ///
     /// * `app_id` - the app id to give to the installed app
     /// * `web_app_bundle` - the web-app bundle to install
-    /// * `membrane_proofs` - the input membrane proofs for the app
-    /// * `network_seed` - the network seed for the app
+    /// * `membrane_proofs` - provides the membrane proofs for the roles of the app, e.g. a `HashMap<RoleName, MembraneProof>`.
+    ///    Not used if `options.defer_membrane_proofs` is set.
+    /// * `options` - the agent key, network seeds and role overrides to install the app with
     pub async fn install_web_app(
         &self,
         app_id: InstalledAppId,
         web_app_bundle: WebAppBundle,
-        membrane_proofs: HashMap<RoleName, MembraneProof>,
-        network_seed: Option<NetworkSeed>,
-    ) -> crate::Result<AppInfo> {
-        self.holochain_runtime
+        membrane_proofs: impl MembraneProofProvider,
+        options: InstallOptions,
+    ) -> crate::Result<AppInstallation> {
+        self.check_publisher_signature(&web_app_bundle, options.publisher_signature.as_deref())?;
+
+        let installed_app_info = self
+            .holochain_runtime
             .filesystem
             .bundle_store
-            .store_web_happ_bundle(app_id.clone(), &web_app_bundle)
+            .store_web_happ_bundle_files(&web_app_bundle)
             .await?;
 
-        let admin_ws = self.admin_websocket().await?;
-        let app_info = install_web_app(
-            &admin_ws,
-            app_id.clone(),
-            web_app_bundle,
-            membrane_proofs,
-            network_seed,
-        )
-        .await?;
+        let installation = self
+            .install_app_bundle(
+                app_id.clone(),
+                web_app_bundle.happ_bundle().await?,
+                installed_app_info,
+                membrane_proofs,
+                options,
+            )
+            .await?;
 
-        self.app_handle.emit("app-installed", app_id)?;
+        log::info!("Installed web-app's ui {app_id:?}");
 
-        Ok(app_info)
+        Ok(installation)
     }
 
     /// Install the given `AppBundle` in the holochain conductor
     ///
     /// * `app_id` - the app id to give to the installed app
     /// * `app_bundle` - the web-app bundle to install
-    /// * `membrane_proofs` - the input membrane proofs for the app
-    /// * `network_seed` - the network seed for the app
+    /// * `membrane_proofs` - provides the membrane proofs for the roles of the app, e.g. a `HashMap<RoleName, MembraneProof>`.
+    ///    Not used if `options.defer_membrane_proofs` is set.
+    /// * `options` - the agent key, network seeds and role overrides to install the app with
     pub async fn install_app(
         &self,
         app_id: InstalledAppId,
         app_bundle: AppBundle,
-        membrane_proofs: HashMap<RoleName, MembraneProof>,
-        network_seed: Option<NetworkSeed>,
-    ) -> crate::Result<AppInfo> {
+        membrane_proofs: impl MembraneProofProvider,
+        options: InstallOptions,
+    ) -> crate::Result<AppInstallation> {
+        let installed_app_info = self
+            .holochain_runtime
+            .filesystem
+            .bundle_store
+            .store_happ_bundle_files(&app_bundle)?;
+
+        self.install_app_bundle(
+            app_id,
+            app_bundle,
+            installed_app_info,
+            membrane_proofs,
+            options,
+        )
+        .await
+    }
+
+    /// Installs the bundle in the conductor, or holds it if its membrane proofs are deferred
+    ///
+    /// The app is only added to the installed apps once the conductor has installed it
+    async fn install_app_bundle(
+        &self,
+        app_id: InstalledAppId,
+        app_bundle: AppBundle,
+        installed_app_info: InstalledAppInfo,
+        membrane_proofs: impl MembraneProofProvider,
+        options: InstallOptions,
+    ) -> crate::Result<AppInstallation> {
+        self.holochain_runtime
+            .filesystem
+            .bundle_store
+            .check_host_label_available(&app_id)?;
         let admin_ws = self.admin_websocket().await?;
 
+        let app_bundle =
+            apply_role_options(app_bundle, options.network_seed.as_ref(), &options.roles)?;
+        let agent_key = self
+            .resolve_agent_key(&admin_ws, options.agent_key_source)
+            .await?;
+
+        if options.defer_membrane_proofs {
+            log::info!("Holding app {app_id} until its membrane proofs are provided");
+            self.holochain_runtime
+                .filesystem
+                .bundle_store
+                .store_app_awaiting_membrane_proofs(
+                    app_id,
+                    &app_bundle,
+                    agent_key.clone(),
+                    installed_app_info,
+                )?;
+            return Ok(AppInstallation::AwaitingMembraneProofs {
+                agent_pub_key: agent_key,
+            });
+        }
+
+        let membrane_proofs =
+            collect_membrane_proofs(&membrane_proofs, &app_id, &agent_key, app_bundle.manifest())
+                .await?;
+        let app_info = install_app(
+            &admin_ws,
+            app_id.clone(),
+            app_bundle,
+            agent_key,
+            membrane_proofs,
+            // Already applied to the roles that don't override it
+            None,
+        )
+        .await?;
         self.holochain_runtime
             .filesystem
             .bundle_store
-            .store_happ_bundle(app_id.clone(), &app_bundle)?;
+            .add_installed_app(app_id.clone(), installed_app_info)?;
+
+        self.on_app_installed(app_id).await?;
+
+        Ok(AppInstallation::Installed(app_info))
+    }
+
+    /// Provides the membrane proofs for an app installed with `InstallOptions::defer_membrane_proofs`,
+    /// finishing its installation
+    ///
+    /// * `app_id` - the app that is awaiting its membrane proofs
+    /// * `membrane_proofs` - provides the membrane proofs for the roles of the app, e.g. a `HashMap<RoleName, MembraneProof>`
+    pub async fn provide_membrane_proofs(
+        &self,
+        app_id: InstalledAppId,
+        membrane_proofs: impl MembraneProofProvider,
+    ) -> crate::Result<AppInfo> {
+        let bundle_store = &self.holochain_runtime.filesystem.bundle_store;
+        let Some((awaiting, app_bundle)) =
+            bundle_store.get_app_awaiting_membrane_proofs(&app_id)?
+        else {
+            return Err(crate::Error::AppNotAwaitingMembraneProofs(app_id));
+        };
 
+        let admin_ws = self.admin_websocket().await?;
+        let membrane_proofs = collect_membrane_proofs(
+            &membrane_proofs,
+            &app_id,
+            &awaiting.agent_pub_key,
+            app_bundle.manifest(),
+        )
+        .await?;
         let app_info = install_app(
             &admin_ws,
             app_id.clone(),
             app_bundle,
+            awaiting.agent_pub_key,
             membrane_proofs,
-            network_seed,
+            None,
         )
         .await?;
+        bundle_store.add_installed_app(app_id.clone(), awaiting.installed_app_info)?;
+        // Only forgotten once it's installed, so that the membrane proofs can be provided again if it failed
+        bundle_store.remove_app_awaiting_membrane_proofs(&app_id)?;
+
+        self.on_app_installed(app_id).await?;
 
-        self.app_handle.emit("app-installed", app_id)?;
         Ok(app_info)
     }
 
+    /// Starts showing the notifications of the app that was just installed in the conductor, and emits `app-installed`
+    async fn on_app_installed(&self, app_id: InstalledAppId) -> crate::Result<()> {
+        // Already installed, so failing to show its notifications doesn't fail the installation
+        if let Err(err) = self.show_notifications_for_app(&app_id).await {
+            log::error!("Failed to show the notifications for app {app_id}: {err:?}");
+        }
+        self.app_handle.emit("app-installed", app_id)?;
+
+        Ok(())
+    }
+
+    /// The apps installed with `InstallOptions::defer_membrane_proofs` that are still awaiting their membrane proofs
+    pub fn list_apps_awaiting_membrane_proofs(&self) -> crate::Result<Vec<InstalledAppId>> {
+        let apps_awaiting_membrane_proofs = self
+            .holochain_runtime
+            .filesystem
+            .bundle_store
+            .apps_awaiting_membrane_proofs_store
+            .get()?;
+
+        Ok(apps_awaiting_membrane_proofs.keys().cloned().collect())
+    }
+
+    /// Uninstalls the given app from the holochain conductor
+    ///
+    /// It also revokes the auth tokens for its app websocket, and detaches its app interface if no other app is using it
+    ///
+    /// * `app_id` - the app to uninstall
+    pub async fn uninstall_app(&self, app_id: InstalledAppId) -> crate::Result<()> {
+        let was_awaiting_membrane_proofs = self
+            .holochain_runtime
+            .filesystem
+            .bundle_store
+            .remove_app_awaiting_membrane_proofs(&app_id)?;
+        if was_awaiting_membrane_proofs {
+            // It was never installed in the conductor
+            self.holochain_runtime
+                .filesystem
+                .bundle_store
+                .remove_app(&app_id)?;
+            return Ok(());
+        }
+
+        let admin_ws = self.admin_websocket().await?;
+
+        self.drop_cached_app_websocket(&app_id).await;
+        self.stop_forwarding_signals_to_windows(&app_id).await;
+        self.stop_notifications_for_app(&app_id).await;
+        self.revoke_app_websocket_auth(&app_id).await?;
+        self.release_app_interface(&admin_ws, &app_id).await?;
+
+        admin_ws
+            .uninstall_app(app_id.clone())
+            .await
+            .map_err(|err| crate::Error::ConductorApiError(err))?;
+
+        self.holochain_runtime
+            .filesystem
+            .bundle_store
+            .remove_app(&app_id)?;
+
+        log::info!("Uninstalled app {app_id:?}");
+        self.app_handle.emit("app-uninstalled", app_id)?;
+
+        Ok(())
+    }
+
     /// Updates the coordinator zomes and UI for the given app with an updated `WebAppBundle`
     ///
+    /// If updating the coordinator zomes fails, the app is rolled back and its UI is not updated.
+    /// Fails with `PublisherSignatureError::MissingSignature` if `HolochainPluginConfig::trusted_publishers` is not empty,
+    /// use `update_signed_web_app()` in that case
+    ///
     /// * `app_id` - the app to update
     /// * `web_app_bundle` - the new version of the web-hApp bundle
     pub async fn update_web_app(
         &self,
         app_id: InstalledAppId,
         web_app_bundle: WebAppBundle,
-    ) -> crate::Result<()> {
-        self.holochain_runtime
-            .filesystem
-            .bundle_store
-            .store_web_happ_bundle(app_id.clone(), &web_app_bundle)
-            .await?;
+    ) -> crate::Result<Vec<CellUpdateResult>> {
+        self.update_signed_web_app(app_id, web_app_bundle, None)
+            .await
+    }
 
+    /// Updates the coordinator zomes and UI for the given app with an updated `WebAppBundle` signed by its publisher
+    ///
+    /// If updating the coordinator zomes fails, the app is rolled back and its UI is not updated
+    ///
+    /// * `app_id` - the app to update
+    /// * `web_app_bundle` - the new version of the web-hApp bundle
+    /// * `publisher_signature` - hex-encoded ed25519 signature by the publisher over the hash of the bundle,
+    ///    required if `HolochainPluginConfig::trusted_publishers` is not empty
+    pub async fn update_signed_web_app(
+        &self,
+        app_id: InstalledAppId,
+        web_app_bundle: WebAppBundle,
+        publisher_signature: Option<String>,
+    ) -> crate::Result<Vec<CellUpdateResult>> {
+        self.check_publisher_signature(&web_app_bundle, publisher_signature.as_deref())?;
+
+        self.update_verified_web_app(app_id, web_app_bundle).await
+    }
+
+    /// Updates the coordinator zomes and UI for the given app with a `WebAppBundle` whose publisher was already verified
+    ///
+    /// * `app_id` - the app to update
+    /// * `web_app_bundle` - the new version of the web-hApp bundle
+    pub(crate) async fn update_verified_web_app(
+        &self,
+        app_id: InstalledAppId,
+        web_app_bundle: WebAppBundle,
+    ) -> crate::Result<Vec<CellUpdateResult>> {
         let admin_ws = self
             .admin_websocket()
             .await
             .map_err(|_err| UpdateAppError::WebsocketError)?;
-        update_app(
+        let results = update_app(
             &admin_ws,
             app_id.clone(),
             web_app_bundle.happ_bundle().await?,
+            self.get_stored_happ_bundle(&app_id),
         )
         .await?;
 
+        self.holochain_runtime
+            .filesystem
+            .bundle_store
+            .store_web_happ_bundle(app_id.clone(), &web_app_bundle)
+            .await?;
+
         self.app_handle.emit("app-updated", app_id)?;
 
-        Ok(())
+        Ok(results)
     }
 
     /// Updates the coordinator zomes for the given app with an updated `AppBundle`
     ///
+    /// All the new DNAs are checked before changing anything, and if updating any of the cells fails,
+    /// the already updated ones are rolled back and the app is enabled again
+    ///
     /// * `app_id` - the app to update
     /// * `app_bundle` - the new version of the hApp bundle
     pub async fn update_app(
         &self,
         app_id: InstalledAppId,
         app_bundle: AppBundle,
-    ) -> std::result::Result<(), UpdateAppError> {
-        let mut admin_ws = self
+    ) -> std::result::Result<Vec<CellUpdateResult>, UpdateAppError> {
+        let admin_ws = self
             .admin_websocket()
             .await
             .map_err(|_err| UpdateAppError::WebsocketError)?;
-        let app_info = update_app(&mut admin_ws, app_id.clone(), app_bundle).await?;
+        let results = update_app(
+            &admin_ws,
+            app_id.clone(),
+            app_bundle.clone(),
+            self.get_stored_happ_bundle(&app_id),
+        )
+        .await?;
+
+        self.holochain_runtime
+            .filesystem
+            .bundle_store
+            .store_happ_bundle(app_id.clone(), &app_bundle)
+            .map_err(|err| UpdateAppError::BundleStoreError(err.to_string()))?;
 
         self.app_handle.emit("app-updated", app_id)?;
-        Ok(app_info)
+        Ok(results)
+    }
+
+    /// Lists what would change in the given app when updating it with `update_app()`, without changing anything
+    ///
+    /// * `app_id` - the app to update
+    /// * `app_bundle` - the new version of the hApp bundle
+    pub async fn plan_app_update(
+        &self,
+        app_id: InstalledAppId,
+        app_bundle: AppBundle,
+    ) -> crate::Result<UpdatePlan> {
+        let admin_ws = self.admin_websocket().await?;
+        let cells = plan_update(&admin_ws, &app_id, app_bundle).await?;
+
+        Ok(UpdatePlan {
+            integrity_changed: cells.iter().any(|cell| cell.integrity_changed),
+            app_id,
+            cells,
+            ui_changed: None,
+        })
+    }
+
+    /// Lists what would change in the given app when updating it with `update_web_app()`, without changing anything
+    ///
+    /// * `app_id` - the app to update
+    /// * `web_app_bundle` - the new version of the web-hApp bundle
+    pub async fn plan_web_app_update(
+        &self,
+        app_id: InstalledAppId,
+        web_app_bundle: WebAppBundle,
+    ) -> crate::Result<UpdatePlan> {
+        let mut plan = self
+            .plan_app_update(app_id.clone(), web_app_bundle.happ_bundle().await?)
+            .await?;
+
+        let installed_ui_hash = self
+            .holochain_runtime
+            .filesystem
+            .bundle_store
+            .installed_apps_store
+            .get()?
+            .get(&app_id)
+            .and_then(|app_info| app_info.web_app_info.as_ref())
+            .map(|web_app_info| web_app_info.ui_hash.clone());
+        if let Some(installed_ui_hash) = installed_ui_hash {
+            let ui_hash = UiStore::ui_hash(&web_app_bundle).await?;
+            plan.ui_changed = Some(!installed_ui_hash.eq(&ui_hash));
+        }
+
+        Ok(plan)
+    }
+
+    /// The hApp bundle the given app was installed or last updated with, used to roll back failed updates
+    fn get_stored_happ_bundle(&self, app_id: &InstalledAppId) -> Option<AppBundle> {
+        match self
+            .holochain_runtime
+            .filesystem
+            .bundle_store
+            .get_happ_bundle(app_id)
+        {
+            Ok(app_bundle) => app_bundle,
+            Err(err) => {
+                log::warn!("Could not read the stored bundle for app {app_id}: {err:?}");
+                None
+            }
+        }
     }
 
     /// Checks whether it is necessary to update the hApp, and if so,
@@ -481,6 +1144,69 @@ pub struct HolochainPluginConfig {
     pub bootstrap_url: Url2,
     pub signal_url: Url2,
     pub holochain_dir: PathBuf,
+    /// Content-Security-Policy for the UIs of the installed web-hApps
+    pub content_security_policy: ContentSecurityPolicyConfig,
+    /// How long the auth tokens for the app websockets are valid for
+    ///
+    /// UIs that need to connect after their token has expired can get a new one with the `get_app_auth_token` command.
+    /// The expiry is exposed in `__HC_LAUNCHER_ENV__.APP_INTERFACE_TOKEN_EXPIRES_AT` and in the `expires_at` of the command's response,
+    /// in milliseconds since the UNIX epoch
+    pub app_auth_token_expiry: Duration,
+    /// How app interfaces are attached to serve the app websockets of the installed apps
+    pub app_interface_strategy: AppInterfaceStrategy,
+    /// Whether to show OS notifications when the installed apps request them through signals
+    ///
+    /// See `HappNotification` for the shape the signals need to have
+    pub enable_notifications: bool,
+    /// How the zome calls made by the hApp UIs are signed
+    pub zome_call_signer: ZomeCallSignerConfig,
+    /// Where to look for new releases of the installed web-hApps
+    pub update_channels: Vec<UpdateChannelConfig>,
+    /// The publishers whose web-hApps can be installed and updated
+    ///
+    /// If not empty, web-hApp bundles are only accepted with a signature by any of these publishers
+    /// over their `BundleStore::web_app_bundle_hash()`
+    pub trusted_publishers: Vec<PublisherKey>,
+    /// Apps shipped with the binary, installed or updated after launching the conductor
+    ///
+    /// Failing to install or update one of them doesn't prevent `holochain-ready`,
+    /// it's emitted with the `bundled-app-failed` event instead
+    pub bundled_apps: Vec<BundledApp>,
+    /// How the health of the conductor is monitored, `None` (the default) to not monitor it
+    ///
+    /// Each check opens a new admin websocket, so monitoring is opt-in,
+    /// and the conductor is only restarted if `HealthMonitorConfig::restart_after_failures` is set
+    pub health_monitor: Option<HealthMonitorConfig>,
+    /// How long to wait for the conductor to start and serve its admin interface before failing with `Error::StartupTimeout`
+    pub startup_timeout: Duration,
+    /// Which port to serve the admin interface of the conductor on, readable afterwards in `HolochainRuntime::admin_port`
+    pub admin_port: AdminPort,
+}
+
+impl HolochainPluginConfig {
+    /// Builds the configuration for the plugin, with the default values for all the optional settings
+    ///
+    /// * `holochain_dir` - the directory where the conductor, the keystore and the hApp bundles are stored
+    /// * `bootstrap_url` - the URL of the bootstrap server
+    /// * `signal_url` - the URL of the signal server
+    pub fn new(holochain_dir: PathBuf, bootstrap_url: Url2, signal_url: Url2) -> Self {
+        HolochainPluginConfig {
+            bootstrap_url,
+            signal_url,
+            holochain_dir,
+            content_security_policy: ContentSecurityPolicyConfig::default(),
+            app_auth_token_expiry: Duration::from_secs(10 * 60),
+            app_interface_strategy: AppInterfaceStrategy::default(),
+            enable_notifications: false,
+            zome_call_signer: ZomeCallSignerConfig::default(),
+            update_channels: vec![],
+            trusted_publishers: vec![],
+            bundled_apps: vec![],
+            health_monitor: None,
+            startup_timeout: Duration::from_secs(40),
+            admin_port: AdminPort::default(),
+        }
+    }
 }
 
 /// Initializes the plugin.
@@ -490,7 +1216,16 @@ pub fn init<R: Runtime>(passphrase: BufRead, config: HolochainPluginConfig) -> T
             commands::sign_zome_call::sign_zome_call,
             commands::open_app::open_app,
             commands::list_apps::list_apps,
-            commands::get_runtime_info::is_holochain_ready
+            commands::list_agents::list_agents,
+            commands::membrane_proofs::provide_membrane_proof,
+            commands::clone_cells::create_clone_cell,
+            commands::clone_cells::enable_clone_cell,
+            commands::clone_cells::disable_clone_cell,
+            commands::clone_cells::delete_clone_cell,
+            commands::get_runtime_info::is_holochain_ready,
+            commands::get_app_auth_token::get_app_auth_token,
+            commands::call_zome::call_zome,
+            commands::notifications::set_app_notifications_muted
         ])
         .register_uri_scheme_protocol("happ", |app_handle, request| {
             log::info!("Received request {}", request.uri().to_string());
@@ -506,33 +1241,14 @@ pub fn init<R: Runtime>(passphrase: BufRead, config: HolochainPluginConfig) -> T
                 // let mutex = app_handle.state::<Mutex<AdminWebsocket>>();
                 // let mut admin_ws = mutex.lock().await;
 
-                let uri_without_protocol = request
-                    .uri()
-                    .to_string()
-                    .split("://")
-                    .map(|s| s.to_string())
-                    .collect::<Vec<String>>()
-                    .get(1)
-                    .expect("Malformed request: not enough items")
-                    .clone();
-                let uri_without_querystring: String = uri_without_protocol
-                    .split("?")
-                    .map(|s| s.to_string())
-                    .collect::<Vec<String>>()
-                    .get(0)
-                    .expect("Malformed request: not enough items 2")
-                    .clone();
-                let uri_components: Vec<String> = uri_without_querystring
-                    .split("/")
-                    .map(|s| s.to_string())
-                    .collect();
-                let lowercase_app_id = uri_components
-                    .get(0)
-                    .expect("Malformed request: not enough items 3");
-                let mut asset_file = PathBuf::new();
-                for i in 1..uri_components.len() {
-                    asset_file = asset_file.join(uri_components[i].clone());
-                }
+                // read_asset is responsible for confining the asset path to the app's UI directory
+                let Some((host_label, asset_file)) = parse_happ_uri(&request.uri().to_string())
+                else {
+                    return response::Builder::new()
+                        .status(tauri::http::StatusCode::BAD_REQUEST)
+                        .body(vec![])
+                        .expect("Failed to build bad request response");
+                };
 
                 let Ok(holochain_plugin) = app_handle.holochain() else {
                     return response::Builder::new()
@@ -545,21 +1261,52 @@ pub fn init<R: Runtime>(passphrase: BufRead, config: HolochainPluginConfig) -> T
                         .expect("Failed to build asset with not internal server error");
                 };
 
+                let app_id = match holochain_plugin
+                    .holochain_runtime
+                    .filesystem
+                    .bundle_store
+                    .get_app_id_for_host_label(&host_label)
+                {
+                    Ok(Some(app_id)) => app_id,
+                    Ok(None) => {
+                        return response::Builder::new()
+                            .status(tauri::http::StatusCode::NOT_FOUND)
+                            .body(vec![])
+                            .expect("Failed to build asset with not found");
+                    }
+                    Err(e) => {
+                        return response::Builder::new()
+                            .status(500)
+                            .body(format!("{:?}", e).into())
+                            .expect("Failed to build body of error response");
+                    }
+                };
+
+                // Serving the UI has no side effects in the conductor: the app interface is assigned when
+                // the window of the app is built, UIs loaded without one have no auth token to connect with anyway
+                let app_websocket_port =
+                    holochain_plugin.assigned_app_websocket_port(&app_id).await;
+                let content_security_policy = holochain_plugin
+                    .config
+                    .content_security_policy
+                    .policy_for_app(
+                        &app_id,
+                        happ_origin(&app_id).to_string().as_str(),
+                        app_websocket_port,
+                    );
+
                 let r = match read_asset(
                     &holochain_plugin.holochain_runtime.filesystem,
-                    lowercase_app_id,
-                    asset_file
-                        .as_os_str()
-                        .to_str()
-                        .expect("Malformed request: not enough items 4")
-                        .to_string(),
+                    &app_id,
+                    asset_file,
                 )
                 .await
                 {
                     Ok(Some((asset, mime_type))) => {
-                        log::info!("Got asset for app with id: {}", lowercase_app_id);
-                        let mut response =
-                            response::Builder::new().status(tauri::http::StatusCode::ACCEPTED);
+                        log::info!("Got asset for app with id: {}", app_id);
+                        let mut response = response::Builder::new()
+                            .status(tauri::http::StatusCode::ACCEPTED)
+                            .header("Content-Security-Policy", content_security_policy);
                         if let Some(mime_type) = mime_type {
                             response = response
                                 .header("Content-Type", format!("{};charset=utf-8", mime_type))
@@ -575,6 +1322,17 @@ pub fn init<R: Runtime>(passphrase: BufRead, config: HolochainPluginConfig) -> T
                         .status(tauri::http::StatusCode::NOT_FOUND)
                         .body(vec![])
                         .expect("Failed to build asset with not found"),
+                    Err(crate::Error::ForbiddenAssetPath(asset_path)) => {
+                        log::warn!(
+                            "App {} tried to access a file outside of its UI: {}",
+                            app_id,
+                            asset_path
+                        );
+                        response::Builder::new()
+                            .status(tauri::http::StatusCode::FORBIDDEN)
+                            .body(vec![])
+                            .expect("Failed to build forbidden response")
+                    }
                     Err(e) => response::Builder::new()
                         .status(500)
                         .body(format!("{:?}", e).into())
@@ -583,6 +1341,27 @@ pub fn init<R: Runtime>(passphrase: BufRead, config: HolochainPluginConfig) -> T
                 r
             })
         })
+        .on_event(|app_handle, event| {
+            if let RunEvent::WindowEvent {
+                label,
+                event: WindowEvent::Destroyed,
+                ..
+            } = event
+            {
+                let app_handle = app_handle.clone();
+                let label = label.clone();
+                tauri::async_runtime::spawn(async move {
+                    let Ok(holochain_plugin) = app_handle.holochain() else {
+                        return;
+                    };
+                    if let Err(err) = holochain_plugin.on_app_window_destroyed(&label).await {
+                        log::error!(
+                            "Failed to clean up after window {label} was destroyed: {err:?}"
+                        );
+                    }
+                });
+            }
+        })
         .setup(|app, _api| {
             let handle = app.clone();
             let result = tauri::async_runtime::block_on(async move {
@@ -597,23 +1376,78 @@ pub fn init<R: Runtime>(passphrase: BufRead, config: HolochainPluginConfig) -> T
 async fn launch_and_setup_holochain<R: Runtime>(
     app_handle: AppHandle<R>,
     passphrase: BufRead,
-    config: HolochainPluginConfig,
+    mut config: HolochainPluginConfig,
 ) -> crate::Result<()> {
     // let http_server_port = portpicker::pick_unused_port().expect("No ports free");
     // http_server::start_http_server(app_handle.clone(), http_server_port).await?;
     // log::info!("Starting http server at port {http_server_port:?}");
 
-    let holochain_runtime = launch_holochain_runtime(passphrase, config).await?;
+    let holochain_runtime = launch_holochain_runtime(passphrase, &config).await?;
+    // Not kept in the config, they are only needed once
+    let bundled_apps = std::mem::take(&mut config.bundled_apps);
 
     let p = HolochainPlugin::<R> {
         app_handle: app_handle.clone(),
         holochain_runtime,
+        config,
     };
 
     // manage state so it is accessible by the commands
     app_handle.manage(p);
 
+    let holochain = app_handle.holochain()?;
+    holochain.load_app_interfaces().await?;
+    if holochain.config.enable_notifications {
+        holochain.start_notifications_bridge().await?;
+    }
+    holochain
+        .install_or_update_bundled_apps(bundled_apps)
+        .await?;
+    holochain.start_update_channels();
+    holochain.start_health_monitor();
+
     app_handle.emit("holochain-ready", ())?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn app_websocket_auths_are_serialized_with_their_expiry() {
+        let app_websocket_auth = AppWebsocketAuth {
+            app_websocket_port: 8888,
+            token: vec![1, 2, 3],
+            expires_at: UNIX_EPOCH + Duration::from_millis(1_700_000_000_123),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&app_websocket_auth).unwrap(),
+            serde_json::json!({
+                "app_websocket_port": 8888,
+                "token": [1, 2, 3],
+                "expires_at": 1_700_000_000_123u64,
+            })
+        );
+    }
+
+    #[test]
+    fn only_connection_errors_drop_the_cached_app_websocket() {
+        assert!(is_connection_error(&ConductorApiError::IoError(
+            std::io::Error::from(std::io::ErrorKind::ConnectionReset)
+        )));
+
+        assert!(!is_connection_error(
+            &ConductorApiError::ExternalApiWireError(
+                holochain_conductor_api::ExternalApiWireError::RibosomeError(String::from(
+                    "Clone limit reached"
+                ))
+            )
+        ));
+        assert!(!is_connection_error(&ConductorApiError::SignZomeCallError(
+            String::from("Agent not found")
+        )));
+    }
+}
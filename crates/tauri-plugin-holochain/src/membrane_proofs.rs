@@ -0,0 +1,331 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use async_std::sync::Mutex;
+use futures::{channel::oneshot, future::BoxFuture, FutureExt};
+use holochain::prelude::{
+    AppManifest, AppRoleManifest, CellProvisioning, MembraneProof, RoleName, SerializedBytes,
+    UnsafeBytes,
+};
+use holochain_client::{AgentPubKey, InstalledAppId};
+use serde::Serialize;
+use tauri::{AppHandle, EventTarget, Manager, Runtime};
+
+/// Event emitted by `UiMembraneProofProvider` to ask the UI for a membrane proof
+pub const MEMBRANE_PROOF_REQUESTED_EVENT: &'static str = "membrane-proof-requested";
+
+/// Everything a `MembraneProofProvider` gets to know to build the membrane proof for a role
+#[derive(Serialize, Debug, Clone)]
+pub struct MembraneProofRequest {
+    pub app_id: InstalledAppId,
+    pub role_name: RoleName,
+    /// The agent the app is being installed for, e.g. to request a proof signed for it
+    pub agent_pub_key: AgentPubKey,
+}
+
+/// Provides the membrane proofs for the roles of an app while it's being installed
+///
+/// It's invoked once for each role in the manifest of the app whose cell is created at install,
+/// so that apps requiring invite codes or signed proofs can be installed without knowing their roles in advance
+pub trait MembraneProofProvider: Send + Sync {
+    /// Returns the membrane proof for the given role, or `None` if the role does not need one
+    fn provide_membrane_proof(
+        &self,
+        request: MembraneProofRequest,
+    ) -> BoxFuture<'_, crate::Result<Option<MembraneProof>>>;
+}
+
+/// Pre-built membrane proofs for the roles that need them
+impl MembraneProofProvider for HashMap<RoleName, MembraneProof> {
+    fn provide_membrane_proof(
+        &self,
+        request: MembraneProofRequest,
+    ) -> BoxFuture<'_, crate::Result<Option<MembraneProof>>> {
+        let membrane_proof = self.get(&request.role_name).cloned();
+        async move { Ok(membrane_proof) }.boxed()
+    }
+}
+
+/// How long `UiMembraneProofProvider` waits for the UI to answer each request by default
+const DEFAULT_MEMBRANE_PROOF_REQUEST_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Asks the membrane proofs from the UI
+///
+/// For each role, a `membrane-proof-requested` event is emitted with a `MembraneProofRequestPayload`, and the
+/// installation waits until the UI answers it with the `provide_membrane_proof` command,
+/// failing with `Error::MembraneProofRequestTimedOut` if it's not answered in time
+pub struct UiMembraneProofProvider<R: Runtime> {
+    app_handle: AppHandle<R>,
+    /// The window to ask the membrane proofs to, or all windows if `None`
+    window_label: Option<String>,
+    timeout: Duration,
+}
+
+impl<R: Runtime> UiMembraneProofProvider<R> {
+    pub fn new(app_handle: AppHandle<R>, window_label: Option<String>) -> Self {
+        UiMembraneProofProvider {
+            app_handle,
+            window_label,
+            timeout: DEFAULT_MEMBRANE_PROOF_REQUEST_TIMEOUT,
+        }
+    }
+
+    /// Sets how long to wait for the UI to answer each request, 5 minutes by default
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// Payload of the `membrane-proof-requested` event
+#[derive(Serialize, Debug, Clone)]
+pub struct MembraneProofRequestPayload {
+    /// Id to pass to the `provide_membrane_proof` command with the answer
+    pub request_id: String,
+    #[serde(flatten)]
+    pub request: MembraneProofRequest,
+}
+
+/// The requests sent to the UI that are waiting for an answer
+pub(crate) type PendingMembraneProofRequests =
+    Arc<Mutex<HashMap<String, oneshot::Sender<Option<MembraneProof>>>>>;
+
+impl<R: Runtime> MembraneProofProvider for UiMembraneProofProvider<R> {
+    fn provide_membrane_proof(
+        &self,
+        request: MembraneProofRequest,
+    ) -> BoxFuture<'_, crate::Result<Option<MembraneProof>>> {
+        async move {
+            let holochain = crate::HolochainExt::holochain(&self.app_handle)?;
+
+            let request_id = nanoid::nanoid!();
+            let (sender, receiver) = oneshot::channel();
+            holochain
+                .holochain_runtime
+                .pending_membrane_proof_requests
+                .lock()
+                .await
+                .insert(request_id.clone(), sender);
+
+            let role_name = request.role_name.clone();
+            let payload = MembraneProofRequestPayload {
+                request_id: request_id.clone(),
+                request,
+            };
+            let emitted = match &self.window_label {
+                Some(label) => self.app_handle.emit_to(
+                    EventTarget::AnyLabel {
+                        label: label.clone(),
+                    },
+                    MEMBRANE_PROOF_REQUESTED_EVENT,
+                    payload,
+                ),
+                None => self
+                    .app_handle
+                    .emit(MEMBRANE_PROOF_REQUESTED_EVENT, payload),
+            };
+            let answer = match emitted {
+                Ok(()) => wait_for_answer(receiver, &request_id, role_name, self.timeout).await,
+                Err(err) => Err(err.into()),
+            };
+
+            if answer.is_err() {
+                // Answering it afterwards fails instead of reaching an installation that gave up on it
+                holochain
+                    .holochain_runtime
+                    .pending_membrane_proof_requests
+                    .lock()
+                    .await
+                    .remove(&request_id);
+            }
+
+            answer
+        }
+        .boxed()
+    }
+}
+
+/// Waits for the UI to answer the request with the given id,
+/// failing with `Error::MembraneProofRequestTimedOut` if it's not answered within `timeout`
+async fn wait_for_answer(
+    receiver: oneshot::Receiver<Option<MembraneProof>>,
+    request_id: &str,
+    role_name: RoleName,
+    timeout: Duration,
+) -> crate::Result<Option<MembraneProof>> {
+    async_std::future::timeout(timeout, receiver)
+        .await
+        .map_err(|_| crate::Error::MembraneProofRequestTimedOut { role_name, timeout })?
+        .map_err(|_| {
+            crate::Error::MembraneProofError(format!(
+                "request {request_id} was dropped before being answered"
+            ))
+        })
+}
+
+/// Whether the conductor creates the cell for the given role when installing the app, and so needs its membrane proof
+///
+/// Clone-only roles and roles whose cell creation is deferred don't use any membrane proof at install
+fn is_provisioned_at_install(role: &AppRoleManifest) -> bool {
+    matches!(
+        role.provisioning,
+        None | Some(CellProvisioning::Create { deferred: false })
+    )
+}
+
+/// Collects the membrane proofs for the roles in the given manifest whose cells are created at install
+pub(crate) async fn collect_membrane_proofs(
+    provider: &dyn MembraneProofProvider,
+    app_id: &InstalledAppId,
+    agent_pub_key: &AgentPubKey,
+    manifest: &AppManifest,
+) -> crate::Result<HashMap<RoleName, MembraneProof>> {
+    let mut membrane_proofs = HashMap::new();
+
+    for role in manifest
+        .app_roles()
+        .into_iter()
+        .filter(is_provisioned_at_install)
+    {
+        let membrane_proof = provider
+            .provide_membrane_proof(MembraneProofRequest {
+                app_id: app_id.clone(),
+                role_name: role.name.clone(),
+                agent_pub_key: agent_pub_key.clone(),
+            })
+            .await?;
+
+        if let Some(membrane_proof) = membrane_proof {
+            membrane_proofs.insert(role.name, membrane_proof);
+        }
+    }
+
+    Ok(membrane_proofs)
+}
+
+/// Builds a membrane proof from the bytes received from the UI
+pub(crate) fn membrane_proof_from_bytes(bytes: Vec<u8>) -> MembraneProof {
+    Arc::new(SerializedBytes::from(UnsafeBytes::from(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex as SyncMutex;
+
+    use holochain::prelude::AppManifestV1;
+
+    use super::*;
+
+    /// Records the roles it's asked for, and provides the same proof for all of them
+    #[derive(Default)]
+    struct RecordingProvider {
+        requested_roles: SyncMutex<Vec<RoleName>>,
+    }
+
+    impl MembraneProofProvider for RecordingProvider {
+        fn provide_membrane_proof(
+            &self,
+            request: MembraneProofRequest,
+        ) -> BoxFuture<'_, crate::Result<Option<MembraneProof>>> {
+            self.requested_roles.lock().unwrap().push(request.role_name);
+            async move { Ok(Some(membrane_proof_from_bytes(vec![1, 2, 3]))) }.boxed()
+        }
+    }
+
+    fn role(role_name: &str, provisioning: Option<CellProvisioning>) -> AppRoleManifest {
+        let mut role = AppRoleManifest::sample(RoleName::from(role_name));
+        role.provisioning = provisioning;
+        role
+    }
+
+    #[test]
+    fn only_roles_provisioned_at_install_are_asked_for_proofs() {
+        let manifest: AppManifest = AppManifestV1 {
+            name: String::from("forum"),
+            description: None,
+            roles: vec![
+                role("default", None),
+                role(
+                    "created",
+                    Some(CellProvisioning::Create { deferred: false }),
+                ),
+                role(
+                    "deferred",
+                    Some(CellProvisioning::Create { deferred: true }),
+                ),
+                role("clone-only", Some(CellProvisioning::CloneOnly)),
+            ],
+        }
+        .into();
+        let provider = RecordingProvider::default();
+
+        let membrane_proofs = tauri::async_runtime::block_on(collect_membrane_proofs(
+            &provider,
+            &String::from("forum"),
+            &AgentPubKey::from_raw_36(vec![0xdb; 36]),
+            &manifest,
+        ))
+        .unwrap();
+
+        let requested_roles = provider.requested_roles.lock().unwrap().clone();
+        assert_eq!(
+            requested_roles,
+            vec![RoleName::from("default"), RoleName::from("created")]
+        );
+        let mut proved_roles: Vec<RoleName> = membrane_proofs.keys().cloned().collect();
+        proved_roles.sort();
+        assert_eq!(
+            proved_roles,
+            vec![RoleName::from("created"), RoleName::from("default")]
+        );
+    }
+
+    #[test]
+    fn unanswered_requests_time_out() {
+        let (_sender, receiver) = oneshot::channel();
+
+        let answer = tauri::async_runtime::block_on(wait_for_answer(
+            receiver,
+            "request",
+            RoleName::from("forum"),
+            Duration::from_millis(100),
+        ));
+
+        assert!(matches!(
+            answer,
+            Err(crate::Error::MembraneProofRequestTimedOut { role_name, .. }) if role_name == "forum"
+        ));
+    }
+
+    #[test]
+    fn dropped_requests_fail_without_waiting_for_the_timeout() {
+        let (sender, receiver) = oneshot::channel();
+        drop(sender);
+
+        let answer = tauri::async_runtime::block_on(wait_for_answer(
+            receiver,
+            "request",
+            RoleName::from("forum"),
+            Duration::from_secs(60),
+        ));
+
+        assert!(matches!(answer, Err(crate::Error::MembraneProofError(_))));
+    }
+
+    #[test]
+    fn answered_requests_return_the_proof() {
+        let (sender, receiver) = oneshot::channel();
+        sender
+            .send(Some(membrane_proof_from_bytes(vec![1, 2, 3])))
+            .unwrap();
+
+        let answer = tauri::async_runtime::block_on(wait_for_answer(
+            receiver,
+            "request",
+            RoleName::from("forum"),
+            Duration::from_secs(60),
+        ))
+        .unwrap();
+
+        assert_eq!(answer, Some(membrane_proof_from_bytes(vec![1, 2, 3])));
+    }
+}
@@ -0,0 +1,345 @@
+use std::future::Future;
+
+use futures::future::BoxFuture;
+use holochain::prelude::AppBundle;
+use holochain_client::{AgentPubKey, AppInfo, InstalledAppId};
+use holochain_types::web_app::WebAppBundle;
+use serde::Serialize;
+use tauri::{Manager, Runtime};
+
+use crate::{
+    AgentKeySource, AppInstallation, CellUpdateResult, HolochainPlugin, InstallOptions,
+    MembraneProofProvider, UpdateAppError,
+};
+
+/// Event emitted when an app was migrated to a new app because the integrity zomes of its new version changed
+pub const APP_MIGRATED_EVENT: &'static str = "app-migrated";
+
+/// Payload of the `app-migrated` event
+#[derive(Serialize, Debug, Clone)]
+pub struct AppMigratedPayload {
+    pub old_app_id: InstalledAppId,
+    pub new_app_id: InstalledAppId,
+}
+
+/// Moves whatever is needed from an app to the new version of it that was installed side by side,
+/// e.g. re-creating the entries of the old DNAs in the new ones
+pub trait AppMigration: Send + Sync {
+    /// Migrates the old app to the new app, which is already installed and enabled with the same agent key
+    fn migrate<'a>(
+        &'a self,
+        old_app_id: &'a InstalledAppId,
+        new_app: &'a AppInfo,
+    ) -> BoxFuture<'a, crate::Result<()>>;
+}
+
+/// Result of `HolochainPlugin::update_app_or_migrate()`
+#[derive(Debug, Clone)]
+pub enum AppUpdate {
+    /// Only the coordinator zomes changed, so the app was updated in place
+    Updated(Vec<CellUpdateResult>),
+    /// The integrity zomes changed, so the new version was installed as a new app and migrated
+    Migrated { new_app: AppInfo },
+}
+
+/// The new version of an app, as it's installed side by side when it can't be updated in place
+enum NewVersion {
+    App(AppBundle),
+    WebApp(WebAppBundle),
+}
+
+/// Checks that the new app can be installed with the given options to migrate the old app to it
+fn check_migration_options(
+    app_id: &InstalledAppId,
+    new_app_id: &InstalledAppId,
+    options: &InstallOptions,
+) -> crate::Result<()> {
+    if options.defer_membrane_proofs {
+        return Err(crate::Error::InvalidInstallOptions(format!(
+            "app {app_id} can't be migrated to {new_app_id} with deferred membrane proofs"
+        )));
+    }
+    Ok(())
+}
+
+/// The options to install the new app with, which always gets the agent key of the old app
+fn migration_install_options(
+    options: InstallOptions,
+    agent_pub_key: AgentPubKey,
+) -> InstallOptions {
+    InstallOptions {
+        agent_key_source: AgentKeySource::Existing { agent_pub_key },
+        ..options
+    }
+}
+
+/// Runs the migration, and if it fails, rolls back the installation of the new app with `uninstall_new_app`
+///
+/// The error of the migration is returned even if rolling back fails too
+async fn migrate_or_roll_back<U, F>(
+    app_id: &InstalledAppId,
+    new_app_id: &InstalledAppId,
+    migration: impl Future<Output = crate::Result<()>>,
+    uninstall_new_app: U,
+) -> crate::Result<()>
+where
+    U: FnOnce() -> F,
+    F: Future<Output = crate::Result<()>>,
+{
+    let Err(err) = migration.await else {
+        return Ok(());
+    };
+
+    log::error!("Failed to migrate app {app_id} to {new_app_id}: {err:?}");
+    if let Err(uninstall_err) = uninstall_new_app().await {
+        log::error!(
+            "Failed to uninstall app {new_app_id} after the failed migration: {uninstall_err:?}"
+        );
+    }
+    Err(err)
+}
+
+impl<R: Runtime> HolochainPlugin<R> {
+    /// Updates the coordinator zomes for the given app with an updated `AppBundle`, or if its integrity zomes changed,
+    /// installs it side by side as a new app with the same agent key and migrates the old app to it
+    ///
+    /// The old app is left installed after the migration, so that it can be uninstalled when appropriate.
+    /// If the migration fails, the new app is uninstalled again
+    ///
+    /// * `app_id` - the app to update
+    /// * `app_bundle` - the new version of the hApp bundle
+    /// * `new_app_id` - the app id to install the new version with if it needs to be migrated
+    /// * `membrane_proofs` - provides the membrane proofs for the roles of the new app
+    /// * `options` - the network seeds and role overrides to install the new app with, usually the ones the old app was
+    ///    installed with. Its `agent_key_source` is ignored, the new app always gets the agent key of the old app.
+    ///    `defer_membrane_proofs` is rejected, since the new app must be installed to migrate the old one to it
+    /// * `migration` - migrates the old app to the new one
+    pub async fn update_app_or_migrate(
+        &self,
+        app_id: InstalledAppId,
+        app_bundle: AppBundle,
+        new_app_id: InstalledAppId,
+        membrane_proofs: impl MembraneProofProvider,
+        options: InstallOptions,
+        migration: impl AppMigration,
+    ) -> crate::Result<AppUpdate> {
+        match self.update_app(app_id.clone(), app_bundle.clone()).await {
+            Err(UpdateAppError::IntegrityChanged { role, .. }) => {
+                log::info!("Integrity zomes for role {role} of app {app_id} changed, migrating it to {new_app_id}");
+                self.migrate_app(
+                    app_id,
+                    NewVersion::App(app_bundle),
+                    new_app_id,
+                    membrane_proofs,
+                    options,
+                    migration,
+                )
+                .await
+            }
+            result => Ok(AppUpdate::Updated(result?)),
+        }
+    }
+
+    /// Updates the coordinator zomes and UI for the given app with an updated `WebAppBundle`, or if its integrity zomes changed,
+    /// installs it side by side as a new app with the same agent key and migrates the old app to it
+    ///
+    /// The old app is left installed after the migration, so that it can be uninstalled when appropriate.
+    /// If the migration fails, the new app is uninstalled again
+    ///
+    /// * `app_id` - the app to update
+    /// * `web_app_bundle` - the new version of the web-hApp bundle
+    /// * `new_app_id` - the app id to install the new version with if it needs to be migrated
+    /// * `membrane_proofs` - provides the membrane proofs for the roles of the new app
+    /// * `options` - the network seeds and role overrides to install the new app with, usually the ones the old app was
+    ///    installed with. Its `agent_key_source` is ignored, the new app always gets the agent key of the old app.
+    ///    Its `publisher_signature` is also checked when updating the app in place
+    ///    `defer_membrane_proofs` is rejected, since the new app must be installed to migrate the old one to it
+    /// * `migration` - migrates the old app to the new one
+    pub async fn update_web_app_or_migrate(
+        &self,
+        app_id: InstalledAppId,
+        web_app_bundle: WebAppBundle,
+        new_app_id: InstalledAppId,
+        membrane_proofs: impl MembraneProofProvider,
+        options: InstallOptions,
+        migration: impl AppMigration,
+    ) -> crate::Result<AppUpdate> {
+        let update = self
+            .update_signed_web_app(
+                app_id.clone(),
+                web_app_bundle.clone(),
+                options.publisher_signature.clone(),
+            )
+            .await;
+        match update {
+            Err(crate::Error::UpdateAppError(UpdateAppError::IntegrityChanged {
+                role, ..
+            })) => {
+                log::info!("Integrity zomes for role {role} of app {app_id} changed, migrating it to {new_app_id}");
+                self.migrate_app(
+                    app_id,
+                    NewVersion::WebApp(web_app_bundle),
+                    new_app_id,
+                    membrane_proofs,
+                    options,
+                    migration,
+                )
+                .await
+            }
+            result => Ok(AppUpdate::Updated(result?)),
+        }
+    }
+
+    async fn migrate_app(
+        &self,
+        app_id: InstalledAppId,
+        new_version: NewVersion,
+        new_app_id: InstalledAppId,
+        membrane_proofs: impl MembraneProofProvider,
+        options: InstallOptions,
+        migration: impl AppMigration,
+    ) -> crate::Result<AppUpdate> {
+        check_migration_options(&app_id, &new_app_id, &options)?;
+
+        // Looked up before installing anything, so that nothing needs to be rolled back if it doesn't exist
+        let admin_ws = self.admin_websocket().await?;
+        let apps = admin_ws
+            .list_apps(None)
+            .await
+            .map_err(|err| crate::Error::ConductorApiError(err))?;
+        let old_app = apps
+            .into_iter()
+            .find(|app| app.installed_app_id.eq(&app_id))
+            .ok_or(UpdateAppError::AppNotFound(app_id.clone()))?;
+
+        let options = migration_install_options(options, old_app.agent_pub_key);
+        let installation = match new_version {
+            NewVersion::App(app_bundle) => {
+                self.install_app(new_app_id.clone(), app_bundle, membrane_proofs, options)
+                    .await?
+            }
+            NewVersion::WebApp(web_app_bundle) => {
+                self.install_web_app(new_app_id.clone(), web_app_bundle, membrane_proofs, options)
+                    .await?
+            }
+        };
+        let AppInstallation::Installed(new_app) = installation else {
+            return Err(crate::Error::MembraneProofError(format!(
+                "app {new_app_id} was not installed because its membrane proofs were deferred"
+            )));
+        };
+
+        migrate_or_roll_back(
+            &app_id,
+            &new_app_id,
+            migration.migrate(&app_id, &new_app),
+            || self.uninstall_app(new_app_id.clone()),
+        )
+        .await?;
+
+        log::info!("Migrated app {app_id} to {new_app_id}");
+        self.app_handle.emit(
+            APP_MIGRATED_EVENT,
+            AppMigratedPayload {
+                old_app_id: app_id,
+                new_app_id,
+            },
+        )?;
+
+        Ok(AppUpdate::Migrated { new_app })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+
+    fn app_id(app_id: &str) -> InstalledAppId {
+        app_id.to_string()
+    }
+
+    #[test]
+    fn deferred_membrane_proofs_are_rejected() {
+        let options = InstallOptions {
+            defer_membrane_proofs: true,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            check_migration_options(&app_id("forum"), &app_id("forum-v2"), &options),
+            Err(crate::Error::InvalidInstallOptions(_))
+        ));
+        assert!(check_migration_options(
+            &app_id("forum"),
+            &app_id("forum-v2"),
+            &InstallOptions::default()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn the_new_app_gets_the_agent_key_of_the_old_app() {
+        let agent_pub_key = AgentPubKey::from_raw_36(vec![0xdb; 36]);
+        let options = InstallOptions {
+            agent_key_source: AgentKeySource::Existing {
+                agent_pub_key: AgentPubKey::from_raw_36(vec![0xaa; 36]),
+            },
+            network_seed: Some(String::from("seed")),
+            ..Default::default()
+        };
+
+        let options = migration_install_options(options, agent_pub_key.clone());
+
+        assert!(matches!(
+            options.agent_key_source,
+            AgentKeySource::Existing { agent_pub_key: key } if key == agent_pub_key
+        ));
+        assert_eq!(options.network_seed, Some(String::from("seed")));
+    }
+
+    #[test]
+    fn failed_migrations_uninstall_the_new_app() {
+        let uninstalled = AtomicBool::new(false);
+
+        let migrated = tauri::async_runtime::block_on(migrate_or_roll_back(
+            &app_id("forum"),
+            &app_id("forum-v2"),
+            async {
+                Err(crate::Error::MembraneProofError(String::from(
+                    "migration failed",
+                )))
+            },
+            || async {
+                uninstalled.store(true, Ordering::SeqCst);
+                Err(crate::Error::ConductorNotRunning)
+            },
+        ));
+
+        assert!(uninstalled.load(Ordering::SeqCst));
+        // The error of the migration, not the one of the rollback
+        assert!(matches!(
+            migrated,
+            Err(crate::Error::MembraneProofError(message)) if message == "migration failed"
+        ));
+    }
+
+    #[test]
+    fn successful_migrations_keep_the_new_app() {
+        let uninstalled = AtomicBool::new(false);
+
+        let migrated = tauri::async_runtime::block_on(migrate_or_roll_back(
+            &app_id("forum"),
+            &app_id("forum-v2"),
+            async { Ok(()) },
+            || async {
+                uninstalled.store(true, Ordering::SeqCst);
+                Ok(())
+            },
+        ));
+
+        assert!(migrated.is_ok());
+        assert!(!uninstalled.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,309 @@
+//! Bridge that turns hApp signals into OS notifications
+//!
+//! When `HolochainPluginConfig::enable_notifications` is set, every signal emitted by a zome with this shape
+//! is shown as an OS notification, unless one of the app's windows is focused or the app was muted:
+//!
+//! ```json
+//! {
+//!   "notification": {
+//!     "title": "New message",
+//!     "body": "Alice: hi!",
+//!     "urgency": "high"
+//!   }
+//! }
+//! ```
+//!
+//! `urgency` is optional, and can be `"low"` (shown silently), `"medium"` (the default) or `"high"`.
+//! The title is shown prefixed with the id of the app, e.g. `forum: New message`, to tell which app it comes from.
+
+use std::collections::HashMap;
+
+use holochain::prelude::{ExternIO, Signal};
+use holochain_client::InstalledAppId;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_notification::{Notification, NotificationExt};
+
+use crate::{filesystem::JsonStore, HolochainExt, HolochainPlugin};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationUrgency {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+/// Notification that a hApp can request to be shown by emitting it as a signal,
+/// wrapped in a `notification` field: `{ "notification": { "title", "body", "urgency" } }`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HappNotification {
+    pub title: String,
+    pub body: String,
+    #[serde(default)]
+    pub urgency: NotificationUrgency,
+}
+
+#[derive(Deserialize, Debug)]
+struct NotificationSignal {
+    notification: HappNotification,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AppNotificationsSettings {
+    pub muted: bool,
+}
+
+pub type NotificationsSettings = HashMap<InstalledAppId, AppNotificationsSettings>;
+
+pub type NotificationsSettingsStore = JsonStore<NotificationsSettings>;
+
+/// Decodes the notification requested by the payload of an app signal, if it has the `HappNotification` shape
+fn decode_notification(payload: ExternIO) -> Option<HappNotification> {
+    let NotificationSignal { notification } = payload.decode().ok()?;
+    Some(notification)
+}
+
+/// Apps are not muted until their notifications are muted explicitly
+fn is_app_muted(settings: &NotificationsSettings, app_id: &InstalledAppId) -> bool {
+    settings
+        .get(app_id)
+        .map(|app_settings| app_settings.muted)
+        .unwrap_or(false)
+}
+
+impl<R: Runtime> HolochainPlugin<R> {
+    /// Mutes or unmutes the OS notifications for the given app
+    ///
+    /// * `app_id` - the app to mute or unmute
+    /// * `muted` - whether the notifications for the app should be muted
+    pub fn set_app_notifications_muted(
+        &self,
+        app_id: InstalledAppId,
+        muted: bool,
+    ) -> crate::Result<()> {
+        self.holochain_runtime
+            .filesystem
+            .bundle_store
+            .notifications_settings_store
+            .update(|settings| {
+                settings.entry(app_id.clone()).or_default().muted = muted;
+            })
+    }
+
+    /// Whether the OS notifications for the given app are muted
+    ///
+    /// * `app_id` - the app to check
+    pub fn are_app_notifications_muted(&self, app_id: &InstalledAppId) -> crate::Result<bool> {
+        let settings = self
+            .holochain_runtime
+            .filesystem
+            .bundle_store
+            .notifications_settings_store
+            .get()?;
+
+        Ok(is_app_muted(&settings, app_id))
+    }
+
+    /// Starts showing the notifications requested by all the installed apps
+    ///
+    /// Failing to subscribe to the signals of an app is logged, without stopping the other apps from showing their notifications
+    pub(crate) async fn start_notifications_bridge(&self) -> crate::Result<()> {
+        if self.app_handle.try_state::<Notification<R>>().is_none() {
+            self.app_handle.plugin(tauri_plugin_notification::init())?;
+        }
+
+        let app_ids: Vec<InstalledAppId> = self
+            .holochain_runtime
+            .filesystem
+            .bundle_store
+            .installed_apps_store
+            .get()?
+            .keys()
+            .cloned()
+            .collect();
+
+        for app_id in app_ids {
+            if let Err(err) = self.show_notifications_for_app(&app_id).await {
+                log::error!("Failed to show the notifications for app {app_id}: {err:?}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Starts showing the notifications requested by the given app, if the notifications bridge is enabled
+    pub(crate) async fn show_notifications_for_app(
+        &self,
+        app_id: &InstalledAppId,
+    ) -> crate::Result<()> {
+        if !self.config.enable_notifications {
+            return Ok(());
+        }
+
+        let mut apps_notifications = self.holochain_runtime.apps_notifications.lock().await;
+        if apps_notifications.contains_key(app_id) {
+            return Ok(());
+        }
+
+        let app_handle = self.app_handle.clone();
+        let notification_app_id = app_id.clone();
+        let subscription = self
+            .on_signal(app_id.clone(), move |signal| {
+                let Signal::App { signal, .. } = signal else {
+                    return;
+                };
+                let Some(notification) = decode_notification(signal.into_inner()) else {
+                    return;
+                };
+
+                let app_handle = app_handle.clone();
+                let app_id = notification_app_id.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(err) = show_notification(&app_handle, &app_id, notification).await {
+                        log::error!("Failed to show notification for app {app_id}: {err:?}");
+                    }
+                });
+            })
+            .await?;
+
+        apps_notifications.insert(app_id.clone(), subscription);
+
+        Ok(())
+    }
+
+    /// Stops showing the notifications requested by the given app
+    pub(crate) async fn stop_notifications_for_app(&self, app_id: &InstalledAppId) {
+        let subscription = self
+            .holochain_runtime
+            .apps_notifications
+            .lock()
+            .await
+            .remove(app_id);
+
+        if let Some(subscription) = subscription {
+            subscription.unsubscribe().await;
+        }
+    }
+}
+
+async fn show_notification<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    app_id: &InstalledAppId,
+    notification: HappNotification,
+) -> crate::Result<()> {
+    let holochain = app_handle.holochain()?;
+
+    if holochain.are_app_notifications_muted(app_id)? {
+        return Ok(());
+    }
+
+    // The user is already looking at the app
+    let app_is_focused = holochain
+        .get_windows_for_app(app_id)
+        .await
+        .into_iter()
+        .filter_map(|label| app_handle.get_webview_window(&label))
+        .any(|window| window.is_focused().unwrap_or(false));
+    if app_is_focused {
+        return Ok(());
+    }
+
+    // Desktop notifications ignore the group and summary, so the title identifies the app on every platform
+    let mut builder = app_handle
+        .notification()
+        .builder()
+        .title(format!("{app_id}: {}", notification.title))
+        .body(notification.body)
+        .group(app_id.clone())
+        .summary(app_id.clone())
+        .extra("appId", app_id.clone());
+
+    builder = match notification.urgency {
+        NotificationUrgency::Low => builder.silent(),
+        NotificationUrgency::Medium => builder,
+        NotificationUrgency::High => builder.sound("default"),
+    };
+
+    builder
+        .show()
+        .map_err(|err| crate::Error::NotificationError(format!("{err:?}")))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn decodes_notification_signals() {
+        let payload = ExternIO::encode(json!({
+            "notification": {
+                "title": "New message",
+                "body": "Alice: hi!",
+                "urgency": "high"
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            decode_notification(payload),
+            Some(HappNotification {
+                title: String::from("New message"),
+                body: String::from("Alice: hi!"),
+                urgency: NotificationUrgency::High,
+            })
+        );
+    }
+
+    #[test]
+    fn urgency_defaults_to_medium() {
+        let payload = ExternIO::encode(json!({
+            "notification": {
+                "title": "New message",
+                "body": "Alice: hi!"
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            decode_notification(payload).map(|notification| notification.urgency),
+            Some(NotificationUrgency::Medium)
+        );
+    }
+
+    #[test]
+    fn ignores_other_signals() {
+        let other_signal = ExternIO::encode(json!({ "type": "NewPost", "title": "hi" })).unwrap();
+        assert_eq!(decode_notification(other_signal), None);
+
+        let missing_body = ExternIO::encode(json!({ "notification": { "title": "hi" } })).unwrap();
+        assert_eq!(decode_notification(missing_body), None);
+
+        let unknown_urgency = ExternIO::encode(json!({
+            "notification": { "title": "hi", "body": "there", "urgency": "urgent" }
+        }))
+        .unwrap();
+        assert_eq!(decode_notification(unknown_urgency), None);
+    }
+
+    #[test]
+    fn only_explicitly_muted_apps_are_muted() {
+        let mut settings = NotificationsSettings::new();
+        settings.insert(
+            String::from("forum"),
+            AppNotificationsSettings { muted: true },
+        );
+        settings.insert(
+            String::from("chat"),
+            AppNotificationsSettings { muted: false },
+        );
+
+        assert!(is_app_muted(&settings, &String::from("forum")));
+        assert!(!is_app_muted(&settings, &String::from("chat")));
+        assert!(!is_app_muted(&settings, &String::from("files")));
+    }
+}
@@ -0,0 +1,108 @@
+use holochain_client::InstalledAppId;
+use url2::Url2;
+
+const MAX_HOST_LABEL_LENGTH: usize = 63;
+const MAX_SLUG_LENGTH: usize = 40;
+const HASH_SUFFIX_LENGTH: usize = 12;
+
+/// The origin from which the UI for the given app is served
+pub fn happ_origin(app_id: &InstalledAppId) -> Url2 {
+    let host = app_id_host_label(app_id);
+    url2::url2!("happ://{host}")
+}
+
+/// Encodes the given app id into a valid host label
+///
+/// Webviews lowercase the hostnames of the URLs they load, and reject characters like spaces,
+/// so the app id can't be used verbatim in the `happ://` origin
+///
+/// App ids that already are valid lowercase host labels are left untouched. Any other app id
+/// is turned into a lowercase slug followed by a hash of the original app id, which keeps
+/// labels for different app ids from colliding. An app id that is equal to the label of another app
+/// is rejected at install. Use `BundleStore::get_app_id_for_host_label()` to decode it back
+pub fn app_id_host_label(app_id: &InstalledAppId) -> String {
+    if is_valid_host_label(app_id) {
+        return app_id.clone();
+    }
+
+    let slug: String = app_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .take(MAX_SLUG_LENGTH)
+        .collect();
+    let slug = slug.trim_matches('-');
+
+    let hash = sha256::digest(app_id.as_str());
+    let hash = &hash[..HASH_SUFFIX_LENGTH];
+
+    if slug.is_empty() {
+        format!("app-{hash}")
+    } else {
+        format!("{slug}-{hash}")
+    }
+}
+
+fn is_valid_host_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= MAX_HOST_LABEL_LENGTH
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_host_labels_are_left_untouched() {
+        assert_eq!(app_id_host_label(&String::from("forum")), "forum");
+        assert_eq!(app_id_host_label(&String::from("my-app-2")), "my-app-2");
+    }
+
+    #[test]
+    fn other_app_ids_are_encoded_into_distinct_host_labels() {
+        let app_ids = [
+            "Forum",
+            "forum ",
+            "My App",
+            "my-app-",
+            "Ñandú",
+            "日本語",
+            "a-very-long-app-id-that-does-not-fit-in-a-single-host-label-at-all",
+        ];
+
+        let labels: Vec<String> = app_ids
+            .iter()
+            .map(|app_id| app_id_host_label(&app_id.to_string()))
+            .collect();
+
+        for label in labels.iter() {
+            assert!(
+                is_valid_host_label(label),
+                "{label} is not a valid host label"
+            );
+            assert_ne!(label, "forum");
+        }
+        for (i, label) in labels.iter().enumerate() {
+            assert!(!labels[i + 1..].contains(label), "{label} is duplicated");
+        }
+        assert!(labels[2].starts_with("my-app-"));
+    }
+
+    #[test]
+    fn host_labels_are_stable() {
+        assert_eq!(
+            app_id_host_label(&String::from("My App")),
+            app_id_host_label(&String::from("My App"))
+        );
+    }
+}
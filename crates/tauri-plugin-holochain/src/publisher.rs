@@ -0,0 +1,164 @@
+use ed25519_dalek::{Signature, VerifyingKey};
+use holochain_types::web_app::WebAppBundle;
+use tauri::Runtime;
+
+use crate::{filesystem::BundleStore, HolochainPlugin};
+
+/// ed25519 public key of a publisher of web-hApps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublisherKey(VerifyingKey);
+
+impl PublisherKey {
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, PublisherSignatureError> {
+        let key = VerifyingKey::from_bytes(bytes)
+            .map_err(|err| PublisherSignatureError::InvalidKey(err.to_string()))?;
+        Ok(PublisherKey(key))
+    }
+
+    /// Parses a hex-encoded public key
+    pub fn from_hex(hex_key: &str) -> Result<Self, PublisherSignatureError> {
+        let bytes: [u8; 32] = hex::decode(hex_key)
+            .map_err(|err| PublisherSignatureError::InvalidKey(err.to_string()))?
+            .try_into()
+            .map_err(|_| PublisherSignatureError::InvalidKey(String::from("expected 32 bytes")))?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0.as_bytes())
+    }
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum PublisherSignatureError {
+    #[error("The web-hApp bundle is not signed by its publisher")]
+    MissingSignature,
+
+    #[error("Malformed publisher signature: {0}")]
+    MalformedSignature(String),
+
+    #[error("Invalid publisher key: {0}")]
+    InvalidKey(String),
+
+    /// The signed message, e.g. the hash of the web-hApp bundle, is not signed by any of the trusted publishers
+    #[error("\"{0}\" is not signed by any of the trusted publishers")]
+    UntrustedSignature(String),
+}
+
+/// Verifies that the given hex-encoded signature over `web_app_bundle_hash`,
+/// as returned by `BundleStore::web_app_bundle_hash()`, was made by any of the trusted publishers
+///
+/// The signed message is the hash as a hex string, so that publishers can sign it without decoding it
+pub fn verify_publisher_signature(
+    web_app_bundle_hash: &str,
+    signature: Option<&str>,
+    trusted_publishers: &[PublisherKey],
+) -> Result<(), PublisherSignatureError> {
+    verify_signature(web_app_bundle_hash, signature, trusted_publishers)
+}
+
+/// The message signed by the publisher of a release: its version and the hash of its bundle, e.g. `0.2.0:c0ffee`,
+/// so that the version of a release can't be swapped without invalidating its signature
+pub fn release_signed_message(version: &str, web_app_bundle_hash: &str) -> String {
+    format!("{version}:{web_app_bundle_hash}")
+}
+
+/// Verifies that the given hex-encoded signature over `release_signed_message()` was made by any of the trusted publishers
+pub fn verify_release_signature(
+    version: &str,
+    web_app_bundle_hash: &str,
+    signature: Option<&str>,
+    trusted_publishers: &[PublisherKey],
+) -> Result<(), PublisherSignatureError> {
+    verify_signature(
+        &release_signed_message(version, web_app_bundle_hash),
+        signature,
+        trusted_publishers,
+    )
+}
+
+fn verify_signature(
+    message: &str,
+    signature: Option<&str>,
+    trusted_publishers: &[PublisherKey],
+) -> Result<(), PublisherSignatureError> {
+    let Some(signature) = signature else {
+        return Err(PublisherSignatureError::MissingSignature);
+    };
+    let bytes: [u8; 64] = hex::decode(signature)
+        .map_err(|err| PublisherSignatureError::MalformedSignature(err.to_string()))?
+        .try_into()
+        .map_err(|_| {
+            PublisherSignatureError::MalformedSignature(String::from("expected 64 bytes"))
+        })?;
+    let signature = Signature::from_bytes(&bytes);
+
+    let trusted = trusted_publishers.iter().any(|publisher| {
+        publisher
+            .0
+            .verify_strict(message.as_bytes(), &signature)
+            .is_ok()
+    });
+    if !trusted {
+        return Err(PublisherSignatureError::UntrustedSignature(
+            message.to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+impl<R: Runtime> HolochainPlugin<R> {
+    /// Checks the publisher signature of the given bundle, if there are any trusted publishers configured
+    pub(crate) fn check_publisher_signature(
+        &self,
+        web_app_bundle: &WebAppBundle,
+        signature: Option<&str>,
+    ) -> crate::Result<()> {
+        if self.config.trusted_publishers.is_empty() {
+            return Ok(());
+        }
+
+        let web_app_bundle_hash = BundleStore::web_app_bundle_hash(web_app_bundle)?;
+        verify_publisher_signature(
+            &web_app_bundle_hash,
+            signature,
+            &self.config.trusted_publishers,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use super::*;
+
+    #[test]
+    fn only_signatures_by_trusted_publishers_are_accepted() {
+        let publisher = SigningKey::from_bytes(&[1; 32]);
+        let other = SigningKey::from_bytes(&[2; 32]);
+        let publisher_key =
+            PublisherKey::from_hex(&hex::encode(publisher.verifying_key().as_bytes())).unwrap();
+
+        let hash = "c0ffee";
+        let signature = hex::encode(publisher.sign(hash.as_bytes()).to_bytes());
+        let other_signature = hex::encode(other.sign(hash.as_bytes()).to_bytes());
+
+        assert!(verify_publisher_signature(hash, Some(&signature), &[publisher_key]).is_ok());
+        assert!(matches!(
+            verify_publisher_signature("decaf", Some(&signature), &[publisher_key]),
+            Err(PublisherSignatureError::UntrustedSignature(_))
+        ));
+        assert!(matches!(
+            verify_publisher_signature(hash, Some(&other_signature), &[publisher_key]),
+            Err(PublisherSignatureError::UntrustedSignature(_))
+        ));
+        assert!(matches!(
+            verify_publisher_signature(hash, None, &[publisher_key]),
+            Err(PublisherSignatureError::MissingSignature)
+        ));
+    }
+}
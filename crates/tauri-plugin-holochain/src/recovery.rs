@@ -0,0 +1,259 @@
+//! Deterministic keys derived from a master seed that can be recovered from a BIP-39 phrase
+//!
+//! The master seed is stored in lair, and the device and agent keys are derived from it with lair's
+//! seed bundle derivation, so that the same recovery phrase always results in the same keys:
+//!
+//! ```text
+//! master seed
+//! ├── 0 (devices)
+//! │   ├── 0 -> device key 0
+//! │   └── 1 -> device key 1
+//! └── 1 (agents)
+//!     ├── 0 -> agent key 0
+//!     └── 1 -> agent key 1
+//! ```
+
+use std::sync::Arc;
+
+use bip39::Mnemonic;
+use holochain_client::AgentPubKey;
+use lair_keystore_api::{lair_client::LairEntryInfo, prelude::X25519PubKey, LairClient};
+use tauri::Runtime;
+
+use crate::{agents::derive_agent_key, AgentKeySource, HolochainPlugin};
+
+/// Tag of the master seed in lair
+pub const MASTER_SEED_TAG: &'static str = "master-seed";
+/// Tag of the seed used to encrypt the master seed when moving it in and out of lair
+pub(crate) const SEED_TRANSPORT_TAG: &'static str = "seed-transport";
+
+const DEVICE_KEYS_INDEX: u32 = 0;
+pub(crate) const AGENT_KEYS_INDEX: u32 = 1;
+
+/// Number of words of the recovery phrases, which encode the 32 bytes of the master seed
+const RECOVERY_PHRASE_WORDS: usize = 24;
+
+impl AgentKeySource {
+    /// Agent key derived from the master seed, recoverable with its recovery phrase
+    ///
+    /// * `agent_index` - the index of the agent, use a different one for each identity
+    pub fn from_master_seed(agent_index: u32) -> Self {
+        AgentKeySource::DerivedFromSeed {
+            seed_tag: MASTER_SEED_TAG.into(),
+            derivation_path: vec![AGENT_KEYS_INDEX, agent_index],
+        }
+    }
+}
+
+impl<R: Runtime> HolochainPlugin<R> {
+    /// Whether a master seed was already created or imported in this device
+    pub async fn has_master_seed(&self) -> crate::Result<bool> {
+        Ok(get_seed_pub_key(&self.lair_client()?, MASTER_SEED_TAG)
+            .await?
+            .is_some())
+    }
+
+    /// Creates a new random master seed, returning its recovery phrase
+    ///
+    /// The recovery phrase should be shown to the user to write it down, since it's the only way to
+    /// recover the keys derived from the master seed in another device
+    pub async fn create_master_seed(&self) -> crate::Result<String> {
+        let mnemonic = Mnemonic::generate(RECOVERY_PHRASE_WORDS)
+            .map_err(|err| crate::Error::InvalidRecoveryPhrase(err.to_string()))?;
+
+        self.store_master_seed(&mnemonic).await?;
+        log::info!("Created master seed");
+
+        Ok(mnemonic.to_string())
+    }
+
+    /// Imports the master seed from the given recovery phrase, e.g. to regain the identity in a new device
+    ///
+    /// * `recovery_phrase` - the phrase returned by `Self::create_master_seed()` or `Self::export_recovery_phrase()`
+    pub async fn import_recovery_phrase(&self, recovery_phrase: String) -> crate::Result<()> {
+        let mnemonic = Mnemonic::parse_normalized(recovery_phrase.trim())
+            .map_err(|err| crate::Error::InvalidRecoveryPhrase(err.to_string()))?;
+        if mnemonic.word_count() != RECOVERY_PHRASE_WORDS {
+            return Err(crate::Error::InvalidRecoveryPhrase(format!(
+                "expected {RECOVERY_PHRASE_WORDS} words, got {}",
+                mnemonic.word_count()
+            )));
+        }
+
+        self.store_master_seed(&mnemonic).await?;
+        log::info!("Imported master seed from recovery phrase");
+
+        Ok(())
+    }
+
+    /// Returns the recovery phrase for the master seed of this device
+    pub async fn export_recovery_phrase(&self) -> crate::Result<String> {
+        let lair_client = self.lair_client()?;
+        if get_seed_pub_key(&lair_client, MASTER_SEED_TAG)
+            .await?
+            .is_none()
+        {
+            return Err(crate::Error::MasterSeedNotFound);
+        }
+        let transport_pub_key = get_or_create_transport_key(&lair_client).await?;
+
+        let (nonce, cipher) = lair_client
+            .export_seed_by_tag(
+                MASTER_SEED_TAG.into(),
+                transport_pub_key.clone(),
+                transport_pub_key.clone(),
+                None,
+            )
+            .await
+            .map_err(|err| crate::Error::LairError(err))?;
+        let seed = lair_client
+            .crypto_box_xsalsa_open_by_pub_key(
+                transport_pub_key.clone(),
+                transport_pub_key,
+                None,
+                nonce,
+                cipher,
+            )
+            .await
+            .map_err(|err| crate::Error::LairError(err))?;
+
+        let mnemonic = Mnemonic::from_entropy(&seed)
+            .map_err(|err| crate::Error::InvalidRecoveryPhrase(err.to_string()))?;
+
+        Ok(mnemonic.to_string())
+    }
+
+    /// Derives the key for the given device from the master seed
+    ///
+    /// * `device_index` - the index of the device, use a different one for each device of the user
+    pub async fn derive_device_key(&self, device_index: u32) -> crate::Result<AgentPubKey> {
+        self.derive_from_master_seed(vec![DEVICE_KEYS_INDEX, device_index])
+            .await
+    }
+
+    /// Derives the agent key with the given index from the master seed
+    ///
+    /// Apps can be installed with this key with `AgentKeySource::from_master_seed(agent_index)`
+    ///
+    /// * `agent_index` - the index of the agent, use a different one for each identity
+    pub async fn derive_agent_key(&self, agent_index: u32) -> crate::Result<AgentPubKey> {
+        self.derive_from_master_seed(vec![AGENT_KEYS_INDEX, agent_index])
+            .await
+    }
+
+    async fn derive_from_master_seed(
+        &self,
+        derivation_path: Vec<u32>,
+    ) -> crate::Result<AgentPubKey> {
+        let lair_client = self.lair_client()?;
+        if get_seed_pub_key(&lair_client, MASTER_SEED_TAG)
+            .await?
+            .is_none()
+        {
+            return Err(crate::Error::MasterSeedNotFound);
+        }
+
+        derive_agent_key(&lair_client, MASTER_SEED_TAG.into(), derivation_path).await
+    }
+
+    /// Imports the seed encoded by the given mnemonic into lair as the master seed
+    async fn store_master_seed(&self, mnemonic: &Mnemonic) -> crate::Result<()> {
+        let lair_client = self.lair_client()?;
+        if get_seed_pub_key(&lair_client, MASTER_SEED_TAG)
+            .await?
+            .is_some()
+        {
+            return Err(crate::Error::MasterSeedAlreadyExists);
+        }
+
+        let transport_pub_key = get_or_create_transport_key(&lair_client).await?;
+
+        // Lair only imports encrypted seeds, so encrypt it to itself with the transport key
+        let seed: Arc<[u8]> = mnemonic.to_entropy().into();
+        let (nonce, cipher) = lair_client
+            .crypto_box_xsalsa_by_pub_key(
+                transport_pub_key.clone(),
+                transport_pub_key.clone(),
+                None,
+                seed,
+            )
+            .await
+            .map_err(|err| crate::Error::LairError(err))?;
+
+        lair_client
+            .import_seed(
+                transport_pub_key.clone(),
+                transport_pub_key,
+                None,
+                nonce,
+                cipher,
+                MASTER_SEED_TAG.into(),
+                true,
+            )
+            .await
+            .map_err(|err| crate::Error::LairError(err))?;
+
+        Ok(())
+    }
+}
+
+async fn get_seed_pub_key(
+    lair_client: &LairClient,
+    tag: &str,
+) -> crate::Result<Option<X25519PubKey>> {
+    let entries = lair_client
+        .list_entries()
+        .await
+        .map_err(|err| crate::Error::LairError(err))?;
+
+    Ok(entries.into_iter().find_map(|entry| match entry {
+        LairEntryInfo::Seed {
+            tag: entry_tag,
+            seed_info,
+        } if entry_tag.as_ref() == tag => Some(seed_info.x25519_pub_key),
+        _ => None,
+    }))
+}
+
+async fn get_or_create_transport_key(lair_client: &LairClient) -> crate::Result<X25519PubKey> {
+    if let Some(pub_key) = get_seed_pub_key(lair_client, SEED_TRANSPORT_TAG).await? {
+        return Ok(pub_key);
+    }
+
+    let seed_info = lair_client
+        .new_seed(SEED_TRANSPORT_TAG.into(), None, false)
+        .await
+        .map_err(|err| crate::Error::LairError(err))?;
+
+    Ok(seed_info.x25519_pub_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovery_phrases_encode_the_whole_seed() {
+        let seed = [7u8; 32];
+        let phrase = Mnemonic::from_entropy(&seed).unwrap().to_string();
+
+        assert_eq!(phrase.split_whitespace().count(), RECOVERY_PHRASE_WORDS);
+
+        let recovered = Mnemonic::parse_normalized(&phrase).unwrap().to_entropy();
+        assert_eq!(recovered, seed.to_vec());
+    }
+
+    #[test]
+    fn agents_from_master_seed_are_derived_under_the_agents_index() {
+        let AgentKeySource::DerivedFromSeed {
+            seed_tag,
+            derivation_path,
+        } = AgentKeySource::from_master_seed(3)
+        else {
+            panic!("expected a derived agent key");
+        };
+
+        assert_eq!(seed_tag, MASTER_SEED_TAG);
+        assert_eq!(derivation_path, vec![AGENT_KEYS_INDEX, 3]);
+    }
+}
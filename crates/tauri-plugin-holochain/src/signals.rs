@@ -0,0 +1,427 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use async_std::sync::Mutex;
+use holochain::prelude::{CellId, Signal, ZomeName};
+use holochain_client::{AppWebsocket, InstalledAppId};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{async_runtime::JoinHandle, AppHandle, EventTarget, Manager, Runtime};
+
+use crate::{json_value::JsonValue, HolochainExt, HolochainPlugin};
+
+/// Event emitted to the windows of an app whenever one of its cells emits a signal
+pub const SIGNAL_EVENT: &'static str = "holochain://signal";
+
+/// How often the connection used to receive signals is checked to be still open
+const SIGNALS_CONNECTION_PROBE_INTERVAL: Duration = Duration::from_secs(10);
+const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Payload of the `holochain://signal` event
+#[derive(Serialize, Clone, Debug)]
+pub struct AppSignalPayload {
+    pub app_id: InstalledAppId,
+    pub cell_id: CellId,
+    pub zome_name: ZomeName,
+    /// The signal emitted by the zome, decoded from msgpack to JSON
+    pub payload: Value,
+}
+
+type SignalHandler = Arc<dyn Fn(Signal) + Send + Sync>;
+type SignalHandlers = Arc<RwLock<HashMap<u64, SignalHandler>>>;
+
+struct AppSignalListeners<T> {
+    handlers: SignalHandlers,
+    /// Keeps the connection that receives the signals of the app open
+    connection_task: T,
+}
+
+/// The handlers subscribed to the signals of each app
+///
+/// Generic over the task that keeps the connection for each app open, so that the bookkeeping can be tested without it
+pub(crate) struct SignalSubscriptions<T = JoinHandle<()>> {
+    next_id: u64,
+    apps: HashMap<InstalledAppId, AppSignalListeners<T>>,
+}
+
+impl<T> Default for SignalSubscriptions<T> {
+    fn default() -> Self {
+        SignalSubscriptions {
+            next_id: 0,
+            apps: HashMap::new(),
+        }
+    }
+}
+
+impl<T> SignalSubscriptions<T> {
+    /// Adds the handler to the subscriptions for the given app, returning the id of the subscription
+    ///
+    /// `connect` is only called for the first subscription for the app, to start dispatching its signals to the given handlers
+    fn subscribe(
+        &mut self,
+        app_id: &InstalledAppId,
+        handler: SignalHandler,
+        connect: impl FnOnce(SignalHandlers) -> T,
+    ) -> crate::Result<u64> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let listeners = self.apps.entry(app_id.clone()).or_insert_with(|| {
+            let handlers: SignalHandlers = Arc::new(RwLock::new(HashMap::new()));
+            let connection_task = connect(handlers.clone());
+            AppSignalListeners {
+                handlers,
+                connection_task,
+            }
+        });
+
+        listeners
+            .handlers
+            .write()
+            .map_err(|err| crate::Error::LockError(format!("{err:?}")))?
+            .insert(id, handler);
+
+        Ok(id)
+    }
+
+    /// Removes the subscription with the given id,
+    /// returning the connection task for the app if there are no more subscriptions for it
+    fn unsubscribe(&mut self, app_id: &InstalledAppId, id: u64) -> Option<T> {
+        let listeners = self.apps.get(app_id)?;
+        let no_handlers_left = match listeners.handlers.write() {
+            Ok(mut handlers) => {
+                handlers.remove(&id);
+                handlers.is_empty()
+            }
+            Err(err) => {
+                log::error!("Failed to remove signal handler: {err:?}");
+                false
+            }
+        };
+
+        if !no_handlers_left {
+            return None;
+        }
+        self.apps
+            .remove(app_id)
+            .map(|listeners| listeners.connection_task)
+    }
+}
+
+/// Doubles the delay before reconnecting after each failed attempt, up to `MAX_RECONNECT_DELAY`
+fn next_reconnect_delay(reconnect_delay: Duration) -> Duration {
+    std::cmp::min(reconnect_delay * 2, MAX_RECONNECT_DELAY)
+}
+
+/// Handle for a subscription created with `HolochainPlugin::on_signal()`
+///
+/// Dropping the handle does not cancel the subscription, use `Self::unsubscribe()` instead
+pub struct SignalSubscription {
+    app_id: InstalledAppId,
+    id: u64,
+    subscriptions: Arc<Mutex<SignalSubscriptions>>,
+}
+
+impl SignalSubscription {
+    /// The app whose signals this subscription receives
+    pub fn app_id(&self) -> &InstalledAppId {
+        &self.app_id
+    }
+
+    /// Stops calling the handler for this subscription
+    ///
+    /// The connection for the app is closed if there are no more subscriptions for it
+    pub async fn unsubscribe(self) {
+        let connection_task = self
+            .subscriptions
+            .lock()
+            .await
+            .unsubscribe(&self.app_id, self.id);
+
+        if let Some(connection_task) = connection_task {
+            connection_task.abort();
+            log::info!("Stopped listening for signals for app {}", self.app_id);
+        }
+    }
+}
+
+impl<R: Runtime> HolochainPlugin<R> {
+    /// Calls the given handler for every signal emitted by the cells of the given app
+    ///
+    /// Signals are received through a long-lived `AppWebsocket` that is shared by all the subscriptions for the app,
+    /// and that reconnects automatically if the connection is lost (e.g. after the conductor restarts)
+    ///
+    /// * `app_id` - the app whose signals to listen to
+    /// * `handler` - the function to call with each signal; it should return quickly, since it runs on the websocket's task
+    pub async fn on_signal<F>(
+        &self,
+        app_id: InstalledAppId,
+        handler: F,
+    ) -> crate::Result<SignalSubscription>
+    where
+        F: Fn(Signal) + Send + Sync + 'static,
+    {
+        let id = self
+            .holochain_runtime
+            .signal_subscriptions
+            .lock()
+            .await
+            .subscribe(&app_id, Arc::new(handler), |handlers| {
+                tauri::async_runtime::spawn(keep_signals_connection(
+                    self.app_handle.clone(),
+                    app_id.clone(),
+                    handlers,
+                ))
+            })?;
+
+        Ok(SignalSubscription {
+            app_id,
+            id,
+            subscriptions: self.holochain_runtime.signal_subscriptions.clone(),
+        })
+    }
+
+    /// Subscribes to the signals of the given app, forwarding them to its windows as `holochain://signal` events
+    ///
+    /// Does nothing if the signals for the app were already being forwarded
+    pub(crate) async fn forward_signals_to_windows(
+        &self,
+        app_id: &InstalledAppId,
+    ) -> crate::Result<()> {
+        let mut apps_forwarding_signals =
+            self.holochain_runtime.apps_forwarding_signals.lock().await;
+        if apps_forwarding_signals.contains_key(app_id) {
+            return Ok(());
+        }
+
+        let app_handle = self.app_handle.clone();
+        let signal_app_id = app_id.clone();
+        let subscription = self
+            .on_signal(app_id.clone(), move |signal| {
+                let Signal::App {
+                    cell_id,
+                    zome_name,
+                    signal,
+                } = signal
+                else {
+                    return;
+                };
+
+                let payload = match signal.into_inner().decode::<JsonValue>() {
+                    Ok(JsonValue(payload)) => payload,
+                    Err(err) => {
+                        log::error!("Failed to decode signal for app {signal_app_id}: {err:?}");
+                        return;
+                    }
+                };
+
+                let signal = AppSignalPayload {
+                    app_id: signal_app_id.clone(),
+                    cell_id,
+                    zome_name,
+                    payload,
+                };
+
+                let app_handle = app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    let Ok(holochain) = app_handle.holochain() else {
+                        return;
+                    };
+                    for label in holochain.get_windows_for_app(&signal.app_id).await {
+                        if let Err(err) = app_handle.emit_to(
+                            EventTarget::AnyLabel { label },
+                            SIGNAL_EVENT,
+                            signal.clone(),
+                        ) {
+                            log::error!("Failed to emit signal: {err:?}");
+                        }
+                    }
+                });
+            })
+            .await?;
+
+        apps_forwarding_signals.insert(app_id.clone(), subscription);
+        log::info!("Forwarding signals for app {app_id} to its windows");
+
+        Ok(())
+    }
+
+    /// Stops forwarding the signals of the given app to its windows
+    pub(crate) async fn stop_forwarding_signals_to_windows(&self, app_id: &InstalledAppId) {
+        let subscription = self
+            .holochain_runtime
+            .apps_forwarding_signals
+            .lock()
+            .await
+            .remove(app_id);
+
+        if let Some(subscription) = subscription {
+            subscription.unsubscribe().await;
+        }
+    }
+}
+
+/// Keeps an `AppWebsocket` open for the given app, dispatching its signals to the handlers
+async fn keep_signals_connection<R: Runtime>(
+    app_handle: AppHandle<R>,
+    app_id: InstalledAppId,
+    handlers: SignalHandlers,
+) {
+    let mut reconnect_delay = MIN_RECONNECT_DELAY;
+
+    loop {
+        match connect_for_signals(&app_handle, &app_id, handlers.clone()).await {
+            Ok(app_ws) => {
+                log::info!("Listening for signals for app {app_id}");
+                reconnect_delay = MIN_RECONNECT_DELAY;
+
+                loop {
+                    async_std::task::sleep(SIGNALS_CONNECTION_PROBE_INTERVAL).await;
+                    if let Err(err) = app_ws.app_info().await {
+                        log::warn!(
+                            "Lost the connection to receive signals for app {app_id}: {err:?}"
+                        );
+                        break;
+                    }
+                }
+            }
+            Err(err) => {
+                log::warn!("Could not connect to receive signals for app {app_id}: {err:?}");
+            }
+        }
+
+        async_std::task::sleep(reconnect_delay).await;
+        reconnect_delay = next_reconnect_delay(reconnect_delay);
+    }
+}
+
+async fn connect_for_signals<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    app_id: &InstalledAppId,
+    handlers: SignalHandlers,
+) -> crate::Result<AppWebsocket> {
+    let app_ws = app_handle
+        .holochain()?
+        .app_websocket(app_id.clone())
+        .await?;
+
+    app_ws
+        .on_signal(move |signal| {
+            let handlers: Vec<SignalHandler> = match handlers.read() {
+                Ok(handlers) => handlers.values().cloned().collect(),
+                Err(err) => {
+                    log::error!("Failed to read signal handlers: {err:?}");
+                    return;
+                }
+            };
+            for handler in handlers {
+                handler(signal.clone());
+            }
+        })
+        .await
+        .map_err(|err| crate::Error::WebsocketConnectionError(format!("{err:?}")))?;
+
+    Ok(app_ws)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handler() -> SignalHandler {
+        Arc::new(|_signal: Signal| {})
+    }
+
+    /// Subscriptions whose connection tasks are the handlers they dispatch the signals to
+    fn subscribe(subscriptions: &mut SignalSubscriptions<SignalHandlers>, app_id: &str) -> u64 {
+        subscriptions
+            .subscribe(&app_id.to_string(), handler(), |handlers| handlers)
+            .unwrap()
+    }
+
+    fn handlers_count(subscriptions: &SignalSubscriptions<SignalHandlers>, app_id: &str) -> usize {
+        subscriptions
+            .apps
+            .get(app_id)
+            .map(|listeners| listeners.handlers.read().unwrap().len())
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn connects_once_per_app() {
+        let mut subscriptions = SignalSubscriptions::<usize>::default();
+        let mut connections = 0;
+
+        for app_id in ["forum", "forum", "chat", "forum"] {
+            subscriptions
+                .subscribe(&app_id.to_string(), handler(), |_| {
+                    connections += 1;
+                    connections
+                })
+                .unwrap();
+        }
+
+        assert_eq!(connections, 2);
+        assert_eq!(subscriptions.apps["forum"].connection_task, 1);
+        assert_eq!(subscriptions.apps["chat"].connection_task, 2);
+    }
+
+    #[test]
+    fn signals_are_dispatched_to_the_handlers_of_their_app() {
+        let mut subscriptions = SignalSubscriptions::default();
+        subscribe(&mut subscriptions, "forum");
+        subscribe(&mut subscriptions, "forum");
+        subscribe(&mut subscriptions, "chat");
+
+        // The connection for each app dispatches its signals to these handlers
+        let forum_handlers = subscriptions.apps["forum"].connection_task.clone();
+        let chat_handlers = subscriptions.apps["chat"].connection_task.clone();
+        assert_eq!(forum_handlers.read().unwrap().len(), 2);
+        assert_eq!(chat_handlers.read().unwrap().len(), 1);
+        assert!(!Arc::ptr_eq(&forum_handlers, &chat_handlers));
+    }
+
+    #[test]
+    fn closes_the_connection_after_the_last_unsubscribe() {
+        let mut subscriptions = SignalSubscriptions::default();
+        let first = subscribe(&mut subscriptions, "forum");
+        let second = subscribe(&mut subscriptions, "forum");
+        let chat = subscribe(&mut subscriptions, "chat");
+        assert_ne!(first, second);
+
+        assert!(subscriptions.unsubscribe(&"forum".into(), first).is_none());
+        assert_eq!(handlers_count(&subscriptions, "forum"), 1);
+
+        // Unsubscribing twice or from another app doesn't remove anything
+        assert!(subscriptions.unsubscribe(&"forum".into(), first).is_none());
+        assert!(subscriptions.unsubscribe(&"forum".into(), chat).is_none());
+        assert_eq!(handlers_count(&subscriptions, "forum"), 1);
+
+        assert!(subscriptions.unsubscribe(&"forum".into(), second).is_some());
+        assert_eq!(handlers_count(&subscriptions, "forum"), 0);
+        assert_eq!(handlers_count(&subscriptions, "chat"), 1);
+
+        assert!(subscriptions.unsubscribe(&"chat".into(), chat).is_some());
+        assert!(subscriptions.apps.is_empty());
+    }
+
+    #[test]
+    fn reconnect_delay_doubles_up_to_the_maximum() {
+        let mut reconnect_delay = MIN_RECONNECT_DELAY;
+        let mut delays = vec![];
+        for _ in 0..10 {
+            reconnect_delay = next_reconnect_delay(reconnect_delay);
+            delays.push(reconnect_delay);
+        }
+
+        assert_eq!(delays[0], Duration::from_secs(1));
+        assert_eq!(delays[1], Duration::from_secs(2));
+        assert_eq!(delays[5], Duration::from_secs(30));
+        assert!(delays.iter().all(|delay| *delay <= MAX_RECONNECT_DELAY));
+        assert_eq!(delays.last(), Some(&MAX_RECONNECT_DELAY));
+    }
+}
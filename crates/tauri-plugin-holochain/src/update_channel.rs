@@ -0,0 +1,445 @@
+use std::{collections::HashMap, time::Duration};
+
+use holochain_client::InstalledAppId;
+use holochain_types::web_app::WebAppBundle;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use tauri::Runtime;
+use url::Url;
+
+use crate::{
+    filesystem::{BundleStore, JsonStore},
+    publisher::{verify_release_signature, PublisherKey},
+    CellUpdateResult, HolochainExt, HolochainPlugin,
+};
+
+/// How long fetching a release manifest may take
+const MANIFEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long downloading the `.webhapp` of a release may take
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// The version of the last release applied through the update channel of each app
+pub(crate) type AppliedReleasesStore = JsonStore<HashMap<InstalledAppId, String>>;
+
+/// Where to look for new releases of an installed web-hApp
+#[derive(Debug, Clone)]
+pub struct UpdateChannelConfig {
+    /// The installed app that this channel updates
+    pub app_id: InstalledAppId,
+    /// URL of the `ReleaseManifest` for the latest release of the app
+    pub manifest_url: Url,
+    /// The publishers whose signature is accepted for the releases of the app
+    ///
+    /// Used instead of `HolochainPluginConfig::trusted_publishers`, which don't need to include them
+    pub publisher_keys: Vec<PublisherKey>,
+    /// How often to check for a new release in the background,
+    /// `None` to only check with `HolochainPlugin::check_for_app_update()`
+    pub check_interval: Option<Duration>,
+}
+
+/// Describes the latest release of a web-hApp, published at the `manifest_url` of its update channel
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseManifest {
+    /// Semver version of the release, which needs to be newer than the last release applied to the app
+    pub version: String,
+    /// URL to download the `.webhapp` for this release from
+    pub webhapp_url: Url,
+    /// Hash of the web-hApp bundle, as returned by `BundleStore::web_app_bundle_hash()`
+    pub web_app_bundle_hash: String,
+    /// Hex-encoded ed25519 signature by the publisher over `{version}:{web_app_bundle_hash}`
+    pub signature: String,
+}
+
+/// Result of checking the update channel of an app
+#[derive(Debug, Clone)]
+pub enum UpdateCheck {
+    /// The installed app is already the latest release
+    UpToDate,
+    /// The latest release was downloaded and the app was updated to it
+    Updated {
+        version: String,
+        cells: Vec<CellUpdateResult>,
+    },
+}
+
+impl<R: Runtime> HolochainPlugin<R> {
+    /// Checks the update channel configured for the given app, and if there is a new release,
+    /// downloads it, verifies its publisher signature and updates the app
+    ///
+    /// The release is only verified against the `publisher_keys` of the channel, not `HolochainPluginConfig::trusted_publishers`,
+    /// and is rejected if its version is older than the last release applied to the app, so that old releases can't be replayed
+    ///
+    /// * `app_id` - the web-hApp to update, which needs to have an `UpdateChannelConfig` in `HolochainPluginConfig::update_channels`
+    pub async fn check_for_app_update(&self, app_id: InstalledAppId) -> crate::Result<UpdateCheck> {
+        let channel = self
+            .config
+            .update_channels
+            .iter()
+            .find(|channel| channel.app_id.eq(&app_id))
+            .ok_or(crate::Error::UpdateChannelError(format!(
+                "no update channel is configured for app {app_id}"
+            )))?;
+
+        let bundle_store = &self.holochain_runtime.filesystem.bundle_store;
+        let installed_web_app_bundle_hash = bundle_store
+            .installed_apps_store
+            .get()?
+            .get(&app_id)
+            .ok_or(crate::Error::AppDoesNotExist(app_id.clone()))?
+            .web_app_info
+            .as_ref()
+            .map(|web_app_info| web_app_info.web_happ_bundle_hash.clone())
+            .ok_or(crate::Error::UpdateChannelError(format!(
+                "app {app_id} was installed without a UI, and update channels only update web-hApps"
+            )))?;
+
+        let manifest =
+            fetch_release_manifest(&http_client(MANIFEST_TIMEOUT)?, &channel.manifest_url).await?;
+        if installed_web_app_bundle_hash.eq(&manifest.web_app_bundle_hash) {
+            return Ok(UpdateCheck::UpToDate);
+        }
+
+        let applied_version = bundle_store
+            .applied_releases_store
+            .get()?
+            .get(&app_id)
+            .cloned();
+        if !is_newer_release(&manifest.version, applied_version.as_deref())? {
+            return Ok(UpdateCheck::UpToDate);
+        }
+
+        log::info!(
+            "Downloading release {} of app {app_id} from {}",
+            manifest.version,
+            manifest.webhapp_url
+        );
+        let web_app_bundle = download_release(
+            &http_client(DOWNLOAD_TIMEOUT)?,
+            &manifest,
+            &channel.publisher_keys,
+        )
+        .await?;
+
+        // Already verified against the publishers of the channel
+        let cells = self
+            .update_verified_web_app(app_id.clone(), web_app_bundle)
+            .await?;
+        bundle_store
+            .applied_releases_store
+            .update(|applied_releases| {
+                applied_releases.insert(app_id.clone(), manifest.version.clone());
+            })?;
+        log::info!("Updated app {app_id} to release {}", manifest.version);
+
+        Ok(UpdateCheck::Updated {
+            version: manifest.version,
+            cells,
+        })
+    }
+
+    /// Spawns the background checks for the update channels that have a `check_interval`
+    pub(crate) fn start_update_channels(&self) {
+        for channel in &self.config.update_channels {
+            let Some(check_interval) = channel.check_interval else {
+                continue;
+            };
+            let app_handle = self.app_handle.clone();
+            let app_id = channel.app_id.clone();
+
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    let Ok(holochain) = app_handle.holochain() else {
+                        return;
+                    };
+                    if let Err(err) = holochain.check_for_app_update(app_id.clone()).await {
+                        log::warn!("Failed to check for updates for app {app_id}: {err:?}");
+                    }
+                    async_std::task::sleep(check_interval).await;
+                }
+            });
+        }
+    }
+}
+
+/// Whether a release with the given version should be applied on top of the last applied release, if any
+///
+/// Errors if the release is older than the last applied one, which is what a replayed old release looks like
+fn is_newer_release(version: &str, applied_version: Option<&str>) -> crate::Result<bool> {
+    let parse = |version: &str| {
+        Version::parse(version).map_err(|err| {
+            crate::Error::UpdateChannelError(format!("invalid release version {version}: {err}"))
+        })
+    };
+    let version = parse(version)?;
+    let Some(applied_version) = applied_version else {
+        return Ok(true);
+    };
+    let applied_version = parse(applied_version)?;
+
+    if version < applied_version {
+        return Err(crate::Error::UpdateChannelError(format!(
+            "release {version} is older than the already applied release {applied_version}"
+        )));
+    }
+
+    Ok(version > applied_version)
+}
+
+/// HTTP client for the requests to update channels, which are given up on after `timeout`
+fn http_client(timeout: Duration) -> crate::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|err| crate::Error::UpdateChannelError(err.to_string()))
+}
+
+/// Fetches the release manifest published at the given URL
+pub(crate) async fn fetch_release_manifest(
+    client: &reqwest::Client,
+    manifest_url: &Url,
+) -> crate::Result<ReleaseManifest> {
+    let response = client
+        .get(manifest_url.clone())
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| crate::Error::UpdateChannelError(err.to_string()))?;
+
+    response
+        .json::<ReleaseManifest>()
+        .await
+        .map_err(|err| crate::Error::UpdateChannelError(err.to_string()))
+}
+
+/// Downloads the web-hApp bundle for the given release, checking that it's the bundle
+/// described in the manifest and that it was signed by one of the given publishers
+pub(crate) async fn download_release(
+    client: &reqwest::Client,
+    manifest: &ReleaseManifest,
+    publisher_keys: &[PublisherKey],
+) -> crate::Result<WebAppBundle> {
+    // Check the signature before downloading anything, the hash of the bundle is checked below
+    verify_release_signature(
+        &manifest.version,
+        &manifest.web_app_bundle_hash,
+        Some(&manifest.signature),
+        publisher_keys,
+    )?;
+
+    let bytes = client
+        .get(manifest.webhapp_url.clone())
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| crate::Error::UpdateChannelError(err.to_string()))?
+        .bytes()
+        .await
+        .map_err(|err| crate::Error::UpdateChannelError(err.to_string()))?;
+
+    let web_app_bundle = WebAppBundle::decode(&bytes)?;
+    let web_app_bundle_hash = BundleStore::web_app_bundle_hash(&web_app_bundle)?;
+    if !web_app_bundle_hash.eq(&manifest.web_app_bundle_hash) {
+        return Err(crate::Error::UpdateChannelError(format!(
+            "the downloaded bundle has hash {web_app_bundle_hash}, but the release manifest expects {}",
+            manifest.web_app_bundle_hash
+        )));
+    }
+
+    Ok(web_app_bundle)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    use ed25519_dalek::{Signer, SigningKey};
+    use holochain_types::web_app::WebAppManifest;
+
+    use super::*;
+    use crate::publisher::release_signed_message;
+
+    /// Serves the given body to the first request made to the returned URL
+    fn serve_once(body: Vec<u8>) -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+        });
+
+        Url::parse(&format!("http://127.0.0.1:{port}/")).unwrap()
+    }
+
+    fn web_app_bundle(name: &str) -> WebAppBundle {
+        mr_bundle::Bundle::new_unchecked(WebAppManifest::current(name.into()), vec![])
+            .unwrap()
+            .into()
+    }
+
+    fn signed_manifest(
+        signing_key: &SigningKey,
+        web_app_bundle: &WebAppBundle,
+        webhapp_url: Url,
+    ) -> ReleaseManifest {
+        let version = String::from("0.2.0");
+        let web_app_bundle_hash = BundleStore::web_app_bundle_hash(web_app_bundle).unwrap();
+        let message = release_signed_message(&version, &web_app_bundle_hash);
+        ReleaseManifest {
+            version,
+            webhapp_url,
+            signature: hex::encode(signing_key.sign(message.as_bytes()).to_bytes()),
+            web_app_bundle_hash,
+        }
+    }
+
+    fn publisher_key(signing_key: &SigningKey) -> PublisherKey {
+        PublisherKey::from_bytes(signing_key.verifying_key().as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn fetches_release_manifest() {
+        let manifest = signed_manifest(
+            &SigningKey::from_bytes(&[1; 32]),
+            &web_app_bundle("forum"),
+            Url::parse("http://127.0.0.1:1/app.webhapp").unwrap(),
+        );
+        let url = serve_once(serde_json::to_vec(&manifest).unwrap());
+
+        let fetched = tauri::async_runtime::block_on(fetch_release_manifest(
+            &http_client(MANIFEST_TIMEOUT).unwrap(),
+            &url,
+        ))
+        .unwrap();
+
+        assert_eq!(fetched, manifest);
+    }
+
+    #[test]
+    fn downloads_releases_matching_the_manifest() {
+        let signing_key = SigningKey::from_bytes(&[1; 32]);
+        let bundle = web_app_bundle("forum");
+        let webhapp_url = serve_once(bundle.encode().unwrap());
+        let manifest = signed_manifest(&signing_key, &bundle, webhapp_url);
+
+        let downloaded = tauri::async_runtime::block_on(download_release(
+            &http_client(DOWNLOAD_TIMEOUT).unwrap(),
+            &manifest,
+            &[publisher_key(&signing_key)],
+        ))
+        .unwrap();
+
+        assert_eq!(
+            BundleStore::web_app_bundle_hash(&downloaded).unwrap(),
+            manifest.web_app_bundle_hash
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_releases() {
+        let signing_key = SigningKey::from_bytes(&[1; 32]);
+        // The server returns a different bundle than the one the publisher signed
+        let webhapp_url = serve_once(web_app_bundle("forum-tampered").encode().unwrap());
+        let manifest = signed_manifest(&signing_key, &web_app_bundle("forum"), webhapp_url);
+
+        let result = tauri::async_runtime::block_on(download_release(
+            &http_client(DOWNLOAD_TIMEOUT).unwrap(),
+            &manifest,
+            &[publisher_key(&signing_key)],
+        ));
+
+        assert!(matches!(result, Err(crate::Error::UpdateChannelError(_))));
+    }
+
+    #[test]
+    fn rejects_releases_from_untrusted_publishers() {
+        let manifest = signed_manifest(
+            &SigningKey::from_bytes(&[1; 32]),
+            &web_app_bundle("forum"),
+            Url::parse("http://127.0.0.1:1/app.webhapp").unwrap(),
+        );
+        let trusted_key = publisher_key(&SigningKey::from_bytes(&[2; 32]));
+
+        let result = tauri::async_runtime::block_on(download_release(
+            &http_client(DOWNLOAD_TIMEOUT).unwrap(),
+            &manifest,
+            &[trusted_key],
+        ));
+
+        assert!(matches!(
+            result,
+            Err(crate::Error::PublisherSignatureError(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_releases_with_a_swapped_version() {
+        let signing_key = SigningKey::from_bytes(&[1; 32]);
+        let mut manifest = signed_manifest(
+            &signing_key,
+            &web_app_bundle("forum"),
+            Url::parse("http://127.0.0.1:1/app.webhapp").unwrap(),
+        );
+        manifest.version = String::from("99.0.0");
+
+        let result = tauri::async_runtime::block_on(download_release(
+            &http_client(DOWNLOAD_TIMEOUT).unwrap(),
+            &manifest,
+            &[publisher_key(&signing_key)],
+        ));
+
+        assert!(matches!(
+            result,
+            Err(crate::Error::PublisherSignatureError(_))
+        ));
+    }
+
+    #[test]
+    fn only_newer_releases_are_applied() {
+        assert!(is_newer_release("0.2.0", None).unwrap());
+        assert!(is_newer_release("0.2.0", Some("0.1.9")).unwrap());
+        assert!(is_newer_release("0.2.0", Some("0.2.0-rc.1")).unwrap());
+        assert!(!is_newer_release("0.2.0", Some("0.2.0")).unwrap());
+        assert!(matches!(
+            is_newer_release("0.1.0", Some("0.2.0")),
+            Err(crate::Error::UpdateChannelError(_))
+        ));
+        assert!(matches!(
+            is_newer_release("latest", None),
+            Err(crate::Error::UpdateChannelError(_))
+        ));
+    }
+
+    #[test]
+    fn stalled_update_channels_time_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!(
+            "http://127.0.0.1:{}/",
+            listener.local_addr().unwrap().port()
+        ))
+        .unwrap();
+        // Accepts the request but never responds
+        std::thread::spawn(move || {
+            let (_stream, _) = listener.accept().unwrap();
+            std::thread::sleep(Duration::from_secs(60));
+        });
+
+        let result = tauri::async_runtime::block_on(fetch_release_manifest(
+            &http_client(Duration::from_millis(200)).unwrap(),
+            &url,
+        ));
+
+        assert!(matches!(result, Err(crate::Error::UpdateChannelError(_))));
+    }
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Source of the zome call signer injected in the hApp windows
+///
+/// Built from `packages/signer` by the build script, or copied from the prebuilt `zome-call-signer.js`
+/// when that's not possible
+const ZOME_CALL_SIGNER_SCRIPT: &'static str =
+    include_str!(concat!(env!("OUT_DIR"), "/zome-call-signer.js"));
+
+/// Configuration for the signing of the zome calls made by the hApp UIs
+#[derive(Clone, Debug)]
+pub struct ZomeCallSignerConfig {
+    /// How long signed zome calls are valid for
+    ///
+    /// The expiry is computed from the clock of the conductor when signing, so that devices
+    /// whose webview clock is skewed still produce valid calls
+    pub zome_call_expiry: Duration,
+    /// Whether to pass the `cap_secret` of the zome call requests made by the UIs through to the signed calls,
+    /// allowing them to make calls authorized by capability grants
+    ///
+    /// If false, zome calls with a `cap_secret` are rejected
+    pub allow_cap_secret: bool,
+}
+
+impl Default for ZomeCallSignerConfig {
+    fn default() -> Self {
+        ZomeCallSignerConfig {
+            zome_call_expiry: Duration::from_secs(5 * 60),
+            allow_cap_secret: false,
+        }
+    }
+}
+
+/// Configuration read by the signer script from `window.__HC_ZOME_CALL_SIGNER_CONFIG__`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SignerScriptConfig {
+    cap_secret_passthrough: bool,
+}
+
+impl ZomeCallSignerConfig {
+    /// Builds the initialization script that installs the zome call signer with this configuration
+    pub(crate) fn initialization_script(&self) -> crate::Result<String> {
+        let config = serde_json::to_string(&SignerScriptConfig {
+            cap_secret_passthrough: self.allow_cap_secret,
+        })?;
+
+        Ok(format!(
+            "window.__HC_ZOME_CALL_SIGNER_CONFIG__ = {config};\n{ZOME_CALL_SIGNER_SCRIPT}"
+        ))
+    }
+}
@@ -1,16 +1,16 @@
-use holochain_types::prelude::AppBundle;
 use lair_keystore::dependencies::sodoken::{BufRead, BufWrite};
-use std::collections::HashMap;
-use std::path::PathBuf;
-use tauri_plugin_holochain::{HolochainExt, HolochainPluginConfig};
+use std::{borrow::Cow, path::PathBuf};
+use tauri_plugin_holochain::{BundledApp, BundledAppBytes, HolochainExt, HolochainPluginConfig};
 use url2::Url2;
-use tauri::AppHandle;
 
 const APP_ID: &'static str = "example";
 
-pub fn example_happ() -> AppBundle {
+pub fn example_happ() -> BundledApp {
     let bytes = include_bytes!("../../workdir/forum.happ");
-    AppBundle::decode(bytes).expect("Failed to decode example happ")
+    BundledApp::new(
+        String::from(APP_ID),
+        BundledAppBytes::Happ(Cow::Borrowed(bytes)),
+    )
 }
 
 pub fn vec_to_locked(mut pass_tmp: Vec<u8>) -> std::io::Result<BufRead> {
@@ -92,18 +92,10 @@ pub fn run() {
         )
         .plugin(tauri_plugin_holochain::init(
             vec_to_locked(vec![]).expect("Can't build passphrase"),
-            HolochainPluginConfig {
-                signal_url: signal_url(),
-                bootstrap_url: bootstrap_url(),
-                holochain_dir: holochain_dir(),
-            },
+            holochain_plugin_config(),
         ))
         .setup(|app| {
-            let handle = app.handle().clone();
-            tauri::async_runtime::block_on(async move {
-                setup(handle).await
-            })?;
-
+            // The plugin installs our hApp or updates it before finishing its set up, so we can just open it
             app.holochain()?
                 .main_window_builder(String::from("main"), false, Some(APP_ID.into()), None)?
                 .build()?;
@@ -114,40 +106,10 @@ pub fn run() {
         .expect("error while running tauri application");
 }
 
-// Very simple setup for now:
-// - On app start, list installed apps:
-//   - If there are no apps installed, this is the first time the app is opened: install our hApp
-//   - If there **are** apps:
-//     - Check if it's necessary to update the coordinators for our hApp
-//       - And do so if it is
-//
-// You can modify this function to suit your needs if they become more complex
-async fn setup(handle: AppHandle) -> anyhow::Result<()> {
-    let admin_ws = handle.holochain()?.admin_websocket().await?;
-
-    let installed_apps = admin_ws
-        .list_apps(None)
-        .await
-        .map_err(|err| tauri_plugin_holochain::Error::ConductorApiError(err))?;
-
-    if installed_apps.len() == 0 {
-        handle
-            .holochain()?
-            .install_app(
-                String::from(APP_ID),
-                example_happ(),
-                HashMap::new(),
-                None,
-            )
-            .await?;
-
-        Ok(())
-    } else {
-        handle.holochain()?.update_app_if_necessary(
-            String::from(APP_ID),
-            example_happ()
-        ).await?;
-
-        Ok(())
-    }
+// Our hApp is installed by the plugin the first time the app is opened,
+// and its coordinators are updated whenever the bundled hApp changes
+fn holochain_plugin_config() -> HolochainPluginConfig {
+    let mut config = HolochainPluginConfig::new(holochain_dir(), bootstrap_url(), signal_url());
+    config.bundled_apps = vec![example_happ()];
+    config
 }
@@ -20,7 +20,15 @@ const randomNonce = async () => randomByteArray(32);
 const randomByteArray = async (length: number) => {
   return globalThis.crypto.getRandomValues(new Uint8Array(length));
 };
-const getNonceExpiration = () => (Date.now() + 5 * 60 * 1000) * 1000; // 5 mins from now in microseconds
+
+interface ZomeCallSignerConfig {
+  // Whether to send the cap_secret of the requests to be signed, instead of dropping it
+  capSecretPassthrough: boolean;
+}
+
+// Injected by tauri-plugin-holochain before this script, from the plugin's config
+const signerConfig = (): ZomeCallSignerConfig =>
+  window["__HC_ZOME_CALL_SIGNER_CONFIG__"] || { capSecretPassthrough: false };
 
 attachConsole().then(() => {
   window.onerror = (e) => console.error(e);
@@ -44,32 +52,37 @@ interface CallZomeRequestSignedTauri
     CallZomeRequestSigned,
     "cap_secret" | "cell_id" | "provenance" | "nonce"
   > {
+  cap_secret: TauriByteArray | null;
   cell_id: [TauriByteArray, TauriByteArray];
   provenance: TauriByteArray;
   nonce: TauriByteArray;
   expires_at: number;
 }
 
+// expires_at is set by the plugin from the conductor's clock, so that skewed webview clocks don't matter
 interface CallZomeRequestUnsignedTauri
   extends Omit<
     CallZomeRequestUnsigned,
-    "cap_secret" | "cell_id" | "provenance" | "nonce"
+    "cap_secret" | "cell_id" | "provenance" | "nonce" | "expires_at"
   > {
+  cap_secret: TauriByteArray | null;
   cell_id: [TauriByteArray, TauriByteArray];
   provenance: TauriByteArray;
   nonce: TauriByteArray;
-  expires_at: number;
 }
 
 const signZomeCallTauri = async (request: CallZomeRequest) => {
   const zomeCallUnsigned: CallZomeRequestUnsignedTauri = {
     provenance: Array.from(request.provenance),
+    cap_secret:
+      signerConfig().capSecretPassthrough && request.cap_secret
+        ? Array.from(request.cap_secret)
+        : null,
     cell_id: [Array.from(request.cell_id[0]), Array.from(request.cell_id[1])],
     zome_name: request.zome_name,
     fn_name: request.fn_name,
     payload: Array.from(encode(request.payload)),
     nonce: Array.from(await randomNonce()),
-    expires_at: getNonceExpiration(),
   };
 
   const signedZomeCallTauri: CallZomeRequestSignedTauri = await invoke(
@@ -81,7 +94,9 @@ const signZomeCallTauri = async (request: CallZomeRequest) => {
 
   const signedZomeCall: CallZomeRequestSigned = {
     provenance: Uint8Array.from(signedZomeCallTauri.provenance),
-    cap_secret: null,
+    cap_secret: signedZomeCallTauri.cap_secret
+      ? Uint8Array.from(signedZomeCallTauri.cap_secret)
+      : null,
     cell_id: [
       Uint8Array.from(signedZomeCallTauri.cell_id[0]),
       Uint8Array.from(signedZomeCallTauri.cell_id[1]),